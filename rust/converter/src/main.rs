@@ -1,7 +1,5 @@
-mod legacy;
-
-use crate::legacy::LegacyPlaylist;
 use anyhow::{bail, Result};
+use blist::legacy::LegacyPlaylist;
 use glob::GlobError;
 use rayon::prelude::*;
 use std::{
@@ -3,16 +3,18 @@ use crate::{
     validation::{BeatmapDifficultyError, BeatmapError},
 };
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{Map, Value};
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::HashSet, fmt};
 
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Beatmap {
     #[serde(rename = "type")]
     pub ty: BeatmapType,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Also read from `dateAdded` like in the legacy format, but a map with both is invalid
+    #[serde(alias = "dateAdded", skip_serializing_if = "Option::is_none")]
     pub date: Option<DateTime<Utc>>,
     #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub difficulties: Vec<BeatmapDifficulty>,
@@ -22,8 +24,8 @@ pub struct Beatmap {
     pub hash: Option<String>,
     #[serde(rename = "levelID", skip_serializing_if = "Option::is_none")]
     pub level_id: Option<String>,
-    #[serde(default = "Map::new", skip_serializing_if = "Map::is_empty")]
-    pub custom_data: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub custom_data: IndexMap<String, Value>,
 }
 
 impl Beatmap {
@@ -35,7 +37,7 @@ impl Beatmap {
             key: Some(key),
             hash: None,
             level_id: None,
-            custom_data: Map::new(),
+            custom_data: IndexMap::new(),
         }
     }
     pub fn new_hash(hash: String) -> Self {
@@ -46,7 +48,7 @@ impl Beatmap {
             key: None,
             hash: Some(hash),
             level_id: None,
-            custom_data: Map::new(),
+            custom_data: IndexMap::new(),
         }
     }
     pub fn new_level_id(level_id: String) -> Self {
@@ -57,10 +59,179 @@ impl Beatmap {
             key: None,
             hash: None,
             level_id: Some(level_id),
-            custom_data: Map::new(),
+            custom_data: IndexMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn new_key_with_difficulties(key: String, difficulties: Vec<BeatmapDifficulty>) -> Self {
+        Self {
+            difficulties,
+            ..Self::new_key(key)
+        }
+    }
+    #[inline]
+    pub fn new_hash_with_difficulties(hash: String, difficulties: Vec<BeatmapDifficulty>) -> Self {
+        Self {
+            difficulties,
+            ..Self::new_hash(hash)
+        }
+    }
+    #[inline]
+    pub fn new_level_id_with_difficulties(
+        level_id: String,
+        difficulties: Vec<BeatmapDifficulty>,
+    ) -> Self {
+        Self {
+            difficulties,
+            ..Self::new_level_id(level_id)
+        }
+    }
+
+    #[inline]
+    pub fn with_date(mut self, date: DateTime<Utc>) -> Self {
+        self.date = Some(date);
+        self
+    }
+    #[inline]
+    pub fn set_date(&mut self, date: DateTime<Utc>) {
+        self.date = Some(date);
+    }
+    #[inline]
+    pub fn clear_date(&mut self) {
+        self.date = None;
+    }
+    #[inline]
+    pub fn date_as_timestamp_secs(&self) -> Option<i64> {
+        self.date.map(|d| d.timestamp())
+    }
+    #[inline]
+    pub fn with_difficulty(mut self, difficulty: BeatmapDifficulty) -> Self {
+        self.difficulties.push(difficulty);
+        self
+    }
+    #[inline]
+    pub fn with_custom_data_entry<S: Into<String>, T: Serialize>(
+        mut self,
+        key: S,
+        value: T,
+    ) -> Result<Self, serde_json::Error> {
+        self.custom_data
+            .insert(key.into(), serde_json::to_value(value)?);
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn key_from_u64(n: u64) -> Self {
+        Self::new_key(format!("{:x}", n))
+    }
+
+    #[inline]
+    pub fn key_as_u64(&self) -> Option<u64> {
+        u64::from_str_radix(self.key.as_deref()?, 16).ok()
+    }
+
+    /// Lowercases the hash of hash beatmaps, which is how BeatSaver stores them
+    #[inline]
+    pub fn normalize_hash(&mut self) {
+        if self.ty == BeatmapType::Hash {
+            if let Some(h) = &mut self.hash {
+                h.make_ascii_lowercase();
+            }
+        }
+    }
+    #[inline]
+    pub fn hash_normalized(&self) -> Option<String> {
+        self.hash.as_ref().map(|h| h.to_ascii_lowercase())
+    }
+
+    /// Sorts the difficulties by rank, then case-insensitively by characteristic
+    pub fn sort_difficulties(&mut self) {
+        self.difficulties
+            .sort_by_cached_key(|d| (d.name_typed(), d.characteristic.to_ascii_lowercase()));
+    }
+    /// Names and characteristics are compared case-insensitively
+    #[inline]
+    pub fn has_difficulty(&self, name: &str, characteristic: &str) -> bool {
+        self.difficulties
+            .iter()
+            .any(|d| d.matches(name, characteristic))
+    }
+    #[inline]
+    pub fn difficulties_for_characteristic<'a>(
+        &'a self,
+        characteristic: &'a str,
+    ) -> impl Iterator<Item = &'a BeatmapDifficulty> {
+        self.difficulties
+            .iter()
+            .filter(move |d| d.characteristic.eq_ignore_ascii_case(characteristic))
+    }
+
+    /// Returns `None` if the field matching the beatmap type is missing
+    pub fn identifier(&self) -> Option<BeatmapIdentifier<'_>> {
+        match self.ty {
+            BeatmapType::Key => self.key.as_deref().map(BeatmapIdentifier::Key),
+            BeatmapType::Hash => self.hash.as_deref().map(BeatmapIdentifier::Hash),
+            BeatmapType::LevelId => self.level_id.as_deref().map(BeatmapIdentifier::LevelId),
+        }
+    }
+    #[inline]
+    pub fn identifier_str(&self) -> Option<&str> {
+        self.identifier().map(|i| i.as_str())
+    }
+
+    /// Removes the identifiers not matching the type, returning the names of the removed fields.
+    ///
+    /// Nothing is removed if the identifier matching the type is missing, since one of the others
+    /// is likely the right one.
+    pub(crate) fn drop_extra_identifiers(&mut self) -> Vec<&'static str> {
+        if self.identifier().is_none() {
+            return Vec::new();
+        }
+        let others = match self.ty {
+            BeatmapType::Key => [("hash", &mut self.hash), ("levelID", &mut self.level_id)],
+            BeatmapType::Hash => [("key", &mut self.key), ("levelID", &mut self.level_id)],
+            BeatmapType::LevelId => [("key", &mut self.key), ("hash", &mut self.hash)],
+        };
+        let mut dropped = Vec::new();
+        for (field, value) in others {
+            if value.take().is_some() {
+                dropped.push(field);
+            }
+        }
+        dropped
+    }
+
+    #[inline]
+    pub fn to_json_object(&self) -> Value {
+        // Beatmaps only contain string keys so serialization can't fail
+        serde_json::to_value(self).unwrap()
+    }
+    #[inline]
+    pub fn from_json_object(v: Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(v)
+    }
+
+    pub(crate) fn normalized_identifier(&self) -> Option<String> {
+        match self.ty {
+            BeatmapType::Key => self.key.as_ref().map(|k| k.to_lowercase()),
+            BeatmapType::Hash => self.hash.as_ref().map(|h| h.to_lowercase()),
+            BeatmapType::LevelId => self.level_id.clone(),
         }
     }
 
+    pub(crate) fn infer_missing_type(map: &mut Map<String, Value>) {
+        if map.contains_key("type") {
+            return;
+        }
+        let ty = match (map.get("hash"), map.get("key")) {
+            (Some(Value::String(h)), _) if h.len() == 40 && utils::str_is_hex(h) => "hash",
+            (_, Some(_)) => "key",
+            _ => "levelID",
+        };
+        map.insert("type".to_owned(), Value::String(ty.to_owned()));
+    }
+
     pub(crate) fn validate(&self) -> Result<(), BeatmapError> {
         match self.ty {
             BeatmapType::Key => {
@@ -89,11 +260,35 @@ impl Beatmap {
             }
         }
 
+        let others = match self.ty {
+            BeatmapType::Key => [("hash", &self.hash), ("levelID", &self.level_id)],
+            BeatmapType::Hash => [("key", &self.key), ("levelID", &self.level_id)],
+            BeatmapType::LevelId => [("key", &self.key), ("hash", &self.hash)],
+        };
+        for &(field, value) in others.iter() {
+            if let Some(v) = value {
+                return Err(BeatmapError::ExtraField {
+                    expected_none: field,
+                    value: v.clone(),
+                });
+            }
+        }
+
         for (idx, d) in self.difficulties.iter().enumerate() {
             if let Err(error) = d.validate() {
                 return Err(BeatmapError::InvalidDifficulty { idx, error });
             }
         }
+        let mut seen = HashSet::with_capacity(self.difficulties.len());
+        for d in &self.difficulties {
+            let id = (d.name.to_lowercase(), d.characteristic.to_lowercase());
+            if !seen.insert(id) {
+                return Err(BeatmapError::DuplicateDifficulty {
+                    name: d.name.clone(),
+                    characteristic: d.characteristic.clone(),
+                });
+            }
+        }
 
         if let Some(k) = &self.key {
             if k.is_empty() || !utils::str_is_hex(k) {
@@ -124,10 +319,19 @@ impl Beatmap {
     }
 }
 
+impl fmt::Display for Beatmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.identifier() {
+            Some(i) => write!(f, "Beatmap({}: {})", self.ty, i.as_str()),
+            None => write!(f, "Beatmap({}: <missing>)", self.ty),
+        }
+    }
+}
+
 impl PartialOrd for Beatmap {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.date.partial_cmp(&other.date)
+        Some(self.cmp(other))
     }
 }
 impl Ord for Beatmap {
@@ -146,6 +350,56 @@ pub enum BeatmapType {
     LevelId,
 }
 
+impl BeatmapType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BeatmapType::Key => "key",
+            BeatmapType::Hash => "hash",
+            BeatmapType::LevelId => "levelID",
+        }
+    }
+}
+
+impl fmt::Display for BeatmapType {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum BeatmapIdentifier<'a> {
+    Key(&'a str),
+    Hash(&'a str),
+    LevelId(&'a str),
+}
+
+impl<'a> BeatmapIdentifier<'a> {
+    #[inline]
+    pub fn ty(&self) -> BeatmapType {
+        match self {
+            BeatmapIdentifier::Key(_) => BeatmapType::Key,
+            BeatmapIdentifier::Hash(_) => BeatmapType::Hash,
+            BeatmapIdentifier::LevelId(_) => BeatmapType::LevelId,
+        }
+    }
+    #[inline]
+    pub fn as_str(&self) -> &'a str {
+        match *self {
+            BeatmapIdentifier::Key(s)
+            | BeatmapIdentifier::Hash(s)
+            | BeatmapIdentifier::LevelId(s) => s,
+        }
+    }
+}
+
+impl fmt::Display for BeatmapIdentifier<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.ty(), self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BeatmapDifficulty {
@@ -154,6 +408,22 @@ pub struct BeatmapDifficulty {
 }
 
 impl BeatmapDifficulty {
+    #[inline]
+    pub fn name_typed(&self) -> BeatmapDifficultyName {
+        self.name.as_str().into()
+    }
+    #[inline]
+    pub fn characteristic_typed(&self) -> BeatmapCharacteristic {
+        self.characteristic.as_str().into()
+    }
+
+    /// Names and characteristics are compared case-insensitively
+    #[inline]
+    pub fn matches(&self, name: &str, characteristic: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            && self.characteristic.eq_ignore_ascii_case(characteristic)
+    }
+
     pub(crate) fn validate(&self) -> Result<(), BeatmapDifficultyError> {
         if utils::str_is_empty_or_has_newlines(&self.name) {
             return Err(BeatmapDifficultyError::InvalidField {
@@ -170,3 +440,370 @@ impl BeatmapDifficulty {
         Ok(())
     }
 }
+
+impl fmt::Display for BeatmapDifficulty {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.name, self.characteristic)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum BeatmapDifficultyName {
+    Easy,
+    Normal,
+    Hard,
+    Expert,
+    ExpertPlus,
+    Custom(String),
+}
+
+impl BeatmapDifficultyName {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+            Self::Expert => "Expert",
+            Self::ExpertPlus => "Expert+",
+            Self::Custom(s) => s,
+        }
+    }
+}
+
+// Only exact spellings are recognized so that any other string round-trips as is
+impl From<&str> for BeatmapDifficultyName {
+    fn from(s: &str) -> Self {
+        match s {
+            "Easy" => Self::Easy,
+            "Normal" => Self::Normal,
+            "Hard" => Self::Hard,
+            "Expert" => Self::Expert,
+            "Expert+" => Self::ExpertPlus,
+            _ => Self::Custom(s.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for BeatmapDifficultyName {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for BeatmapDifficultyName {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+impl<'de> Deserialize<'de> for BeatmapDifficultyName {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(String::deserialize(deserializer)?.as_str().into())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum BeatmapCharacteristic {
+    Standard,
+    OneSaber,
+    NoArrows,
+    NinetyDegree,
+    ThreeSixtyDegree,
+    Lightshow,
+    Lawless,
+    Custom(String),
+}
+
+impl BeatmapCharacteristic {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Standard => "Standard",
+            Self::OneSaber => "OneSaber",
+            Self::NoArrows => "NoArrows",
+            Self::NinetyDegree => "90Degree",
+            Self::ThreeSixtyDegree => "360Degree",
+            Self::Lightshow => "Lightshow",
+            Self::Lawless => "Lawless",
+            Self::Custom(s) => s,
+        }
+    }
+}
+
+impl From<&str> for BeatmapCharacteristic {
+    fn from(s: &str) -> Self {
+        match s {
+            "Standard" => Self::Standard,
+            "OneSaber" => Self::OneSaber,
+            "NoArrows" => Self::NoArrows,
+            "90Degree" => Self::NinetyDegree,
+            "360Degree" => Self::ThreeSixtyDegree,
+            "Lightshow" => Self::Lightshow,
+            "Lawless" => Self::Lawless,
+            _ => Self::Custom(s.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for BeatmapCharacteristic {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for BeatmapCharacteristic {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+impl<'de> Deserialize<'de> for BeatmapCharacteristic {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(String::deserialize(deserializer)?.as_str().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beatmap::{
+        Beatmap, BeatmapCharacteristic, BeatmapDifficulty, BeatmapDifficultyName,
+        BeatmapIdentifier, BeatmapType,
+    };
+    use serde_json::Value;
+
+    #[test]
+    fn typed_difficulties() {
+        let mut names = vec![
+            BeatmapDifficultyName::ExpertPlus,
+            BeatmapDifficultyName::Easy,
+            BeatmapDifficultyName::Expert,
+            BeatmapDifficultyName::Hard,
+            BeatmapDifficultyName::Normal,
+        ];
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                BeatmapDifficultyName::Easy,
+                BeatmapDifficultyName::Normal,
+                BeatmapDifficultyName::Hard,
+                BeatmapDifficultyName::Expert,
+                BeatmapDifficultyName::ExpertPlus,
+            ]
+        );
+
+        let difficulty = BeatmapDifficulty {
+            name: "Exprt+".to_owned(),
+            characteristic: "Standard".to_owned(),
+        };
+        assert_eq!(
+            difficulty.name_typed(),
+            BeatmapDifficultyName::Custom("Exprt+".to_owned())
+        );
+        assert_eq!(
+            difficulty.characteristic_typed(),
+            BeatmapCharacteristic::Standard
+        );
+        assert_eq!(
+            BeatmapCharacteristic::from("Rhythm"),
+            BeatmapCharacteristic::Custom("Rhythm".to_owned())
+        );
+        // Other spellings are kept as is so they serialize back unchanged
+        for name in ["standard", "ONESABER", "90degree"] {
+            let typed = BeatmapCharacteristic::from(name);
+            assert_eq!(typed, BeatmapCharacteristic::Custom(name.to_owned()));
+            assert_eq!(typed.as_str(), name);
+        }
+
+        assert_eq!(
+            serde_json::to_value(BeatmapDifficultyName::ExpertPlus).unwrap(),
+            Value::String("Expert+".to_owned())
+        );
+        assert_eq!(
+            serde_json::from_value::<BeatmapDifficultyName>(Value::String("Expert+".to_owned()))
+                .unwrap(),
+            BeatmapDifficultyName::ExpertPlus
+        );
+        for name in ["expert+", "EXPERT", "ExpertPlus"] {
+            let typed =
+                serde_json::from_value::<BeatmapDifficultyName>(Value::String(name.to_owned()))
+                    .unwrap();
+            assert_eq!(typed, BeatmapDifficultyName::Custom(name.to_owned()));
+            assert_eq!(
+                serde_json::to_value(typed).unwrap(),
+                Value::String(name.to_owned())
+            );
+        }
+        assert_eq!(
+            serde_json::to_value(BeatmapCharacteristic::ThreeSixtyDegree).unwrap(),
+            Value::String("360Degree".to_owned())
+        );
+    }
+
+    #[test]
+    fn identifier() {
+        let key = Beatmap::new_key("1a2b".to_owned());
+        assert_eq!(key.identifier(), Some(BeatmapIdentifier::Key("1a2b")));
+        assert_eq!(key.identifier().unwrap().to_string(), "key:1a2b");
+
+        let hash = Beatmap::new_hash("0123456789abcdef0123456789abcdef01234567".to_owned());
+        assert_eq!(
+            hash.identifier().unwrap().to_string(),
+            "hash:0123456789abcdef0123456789abcdef01234567"
+        );
+
+        let level_id = Beatmap::new_level_id("custom_level_abc".to_owned());
+        assert_eq!(
+            level_id.identifier(),
+            Some(BeatmapIdentifier::LevelId("custom_level_abc"))
+        );
+        assert_eq!(level_id.identifier_str(), Some("custom_level_abc"));
+        assert_eq!(
+            level_id.identifier().unwrap().to_string(),
+            "levelID:custom_level_abc"
+        );
+
+        let malformed = Beatmap {
+            key: None,
+            hash: Some("0123456789abcdef0123456789abcdef01234567".to_owned()),
+            ..Beatmap::new_key("1a2b".to_owned())
+        };
+        assert_eq!(malformed.identifier(), None);
+        assert_eq!(malformed.identifier_str(), None);
+    }
+
+    #[test]
+    fn numeric_keys() {
+        for &n in &[0, 0x1a2b, u64::MAX] {
+            let map = Beatmap::key_from_u64(n);
+            assert!(map.validate().is_ok());
+            assert_eq!(map.key_as_u64(), Some(n));
+        }
+        assert_eq!(Beatmap::key_from_u64(0x1a2b).key.as_deref(), Some("1a2b"));
+        assert_eq!(
+            Beatmap::new_key("1A2B".to_owned()).key_as_u64(),
+            Some(0x1a2b)
+        );
+
+        for key in &["", "not hex", "1a2g", "10000000000000000"] {
+            assert_eq!(Beatmap::new_key(key.to_string()).key_as_u64(), None);
+        }
+        let hash = Beatmap::new_hash("0123456789abcdef0123456789abcdef01234567".to_owned());
+        assert_eq!(hash.key_as_u64(), None);
+    }
+
+    #[test]
+    fn difficulties() {
+        let difficulty = |name: &str, characteristic: &str| BeatmapDifficulty {
+            name: name.to_owned(),
+            characteristic: characteristic.to_owned(),
+        };
+        let map = Beatmap::new_key("1a2b".to_owned())
+            .with_difficulty(difficulty("Expert", "Standard"))
+            .with_difficulty(difficulty("Hard", "OneSaber"))
+            .with_difficulty(difficulty("ExpertPlus", "Standard"));
+
+        assert!(map.has_difficulty("Expert", "Standard"));
+        assert!(map.has_difficulty("expertplus", "STANDARD"));
+        assert!(!map.has_difficulty("Hard", "Standard"));
+        assert_eq!(
+            map.difficulties_for_characteristic("standard")
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Expert", "ExpertPlus"]
+        );
+
+        let mut sorted = map.clone();
+        sorted.sort_difficulties();
+        assert_eq!(
+            sorted.difficulties,
+            [
+                difficulty("Hard", "OneSaber"),
+                difficulty("Expert", "Standard"),
+                difficulty("ExpertPlus", "Standard"),
+            ]
+        );
+
+        let empty = Beatmap::new_key("1a2b".to_owned());
+        assert!(!empty.has_difficulty("Expert", "Standard"));
+        assert_eq!(empty.difficulties_for_characteristic("Standard").count(), 0);
+    }
+
+    #[test]
+    fn display() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(
+            Beatmap::new_key("1a2b".to_owned()).to_string(),
+            "Beatmap(key: 1a2b)"
+        );
+        assert_eq!(
+            Beatmap::new_hash(hash.to_owned()).to_string(),
+            format!("Beatmap(hash: {})", hash)
+        );
+        assert_eq!(
+            Beatmap::new_level_id("CustomLevel_MyLevel".to_owned()).to_string(),
+            "Beatmap(levelID: CustomLevel_MyLevel)"
+        );
+        let malformed = Beatmap {
+            key: None,
+            ..Beatmap::new_key("1a2b".to_owned())
+        };
+        assert_eq!(malformed.to_string(), "Beatmap(key: <missing>)");
+
+        let difficulty = BeatmapDifficulty {
+            name: "Expert+".to_owned(),
+            characteristic: "Standard".to_owned(),
+        };
+        assert_eq!(difficulty.to_string(), "Expert+/Standard");
+        assert_eq!(BeatmapType::LevelId.to_string(), "levelID");
+    }
+
+    #[test]
+    fn dates() {
+        let date = "2020-05-03T12:00:00Z".parse().unwrap();
+        let mut map = Beatmap::new_key("1a2b".to_owned()).with_date(date);
+        assert_eq!(map.date, Some(date));
+        assert_eq!(map.date_as_timestamp_secs(), Some(1588507200));
+
+        let later = Beatmap::new_key("1a2b".to_owned());
+        assert!(map < later);
+
+        map.clear_date();
+        assert_eq!(map.date_as_timestamp_secs(), None);
+        assert!(map < later);
+        let json = map.to_json_object();
+        assert!(json.get("date").is_none());
+        assert_eq!(Beatmap::from_json_object(json).unwrap(), map);
+
+        map.set_date(date);
+        assert_eq!(map.date, Some(date));
+    }
+
+    #[test]
+    fn date_added_alias() {
+        let date = "2020-05-03T12:00:00Z".parse().unwrap();
+        let json = serde_json::json!({
+            "type": "key",
+            "key": "1a2b",
+            "dateAdded": "2020-05-03T12:00:00Z",
+        });
+        let map = Beatmap::from_json_object(json).unwrap();
+        assert_eq!(map.date, Some(date));
+        assert!(map.custom_data.is_empty());
+        assert!(map.to_json_object().get("dateAdded").is_none());
+
+        // Both names refer to the same field, so having both is an error
+        let json = serde_json::json!({
+            "type": "key",
+            "key": "1a2b",
+            "date": "2020-05-03T12:00:00Z",
+            "dateAdded": "2020-05-03T12:00:00Z",
+        });
+        let error = Beatmap::from_json_object(json).unwrap_err();
+        assert!(error.to_string().contains("duplicate field `date`"));
+    }
+}
@@ -6,8 +6,17 @@ pub enum Error {
     Zip(#[from] zip::result::ZipError),
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("base64 error: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[cfg(feature = "image")]
+    #[error("image error: {0}")]
+    Image(#[from] image::ImageError),
     #[error("io error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("invalid zip archive password")]
+    InvalidPassword,
+    #[error("zip archive has more than one entry named `{name}`")]
+    DuplicateZipEntry { name: String },
     #[error("validation error: {0}")]
     Validation(#[from] crate::validation::PlaylistError),
 }
@@ -0,0 +1,126 @@
+use crate::{Beatmap, Error, Playlist};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+pub fn to_ndjson<W: Write>(playlist: &Playlist, mut writer: W) -> Result<(), Error> {
+    let mut metadata = serde_json::to_value(playlist)?;
+    if let Value::Object(m) = &mut metadata {
+        m.remove("maps");
+    }
+    serde_json::to_writer(&mut writer, &metadata)?;
+    writer.write_all(b"\n")?;
+
+    for m in &playlist.maps {
+        serde_json::to_writer(&mut writer, m)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+pub fn from_ndjson<R: Read>(reader: R) -> Result<Playlist, Error> {
+    let mut lines = BufReader::new(reader).lines();
+
+    let mut metadata: Value = match lines.next() {
+        Some(l) => serde_json::from_str(&l?)?,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "missing playlist metadata line",
+            )
+            .into())
+        }
+    };
+    if let Value::Object(m) = &mut metadata {
+        m.insert("maps".to_owned(), Value::Array(Vec::new()));
+    }
+    let mut playlist: Playlist = serde_json::from_value(metadata)?;
+
+    for l in lines {
+        let l = l?;
+        if l.trim().is_empty() {
+            continue;
+        }
+        let map: Beatmap = serde_json::from_str(&l)?;
+        playlist.maps.push(map);
+    }
+
+    playlist.drop_extra_identifiers();
+    playlist.validate_inner(false)?;
+    Ok(playlist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_ndjson, to_ndjson};
+    use crate::{validation::PlaylistError, Beatmap, Error, Playlist};
+    use std::io;
+
+    #[test]
+    fn round_trip() {
+        let mut playlist = Playlist::new("playlist".to_owned())
+            .with_author("author".to_owned())
+            .with_custom_data_entry("custom", true)
+            .unwrap();
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+        playlist
+            .maps
+            .push(Beatmap::new_level_id("level ID".to_owned()));
+
+        let mut ndjson = Vec::new();
+        to_ndjson(&playlist, &mut ndjson).unwrap();
+        assert_eq!(ndjson.iter().filter(|&&b| b == b'\n').count(), 3);
+        assert_eq!(from_ndjson(ndjson.as_slice()).unwrap(), playlist);
+    }
+
+    #[test]
+    fn blank_lines() {
+        let ndjson = b"{\"title\":\"playlist\"}\n\n{\"type\":\"key\",\"key\":\"1a2b\"}\n  \n";
+        let playlist = from_ndjson(&ndjson[..]).unwrap();
+        assert_eq!(playlist.title, "playlist");
+        assert_eq!(
+            playlist.maps,
+            [Beatmap {
+                date: None,
+                ..Beatmap::new_key("1a2b".to_owned())
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        match from_ndjson(&b""[..]) {
+            Err(Error::IO(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(matches!(
+            from_ndjson(&b"\n{\"title\":\"playlist\"}\n"[..]),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            from_ndjson(&b"{\"author\":\"author\"}\n"[..]),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            from_ndjson(&b"{\"title\":\"\"}\n"[..]),
+            Err(Error::Validation(PlaylistError::InvalidField {
+                field: "title",
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn invalid_map() {
+        assert!(matches!(
+            from_ndjson(&b"{\"title\":\"playlist\"}\n{\"type\":\"key\"\n"[..]),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            from_ndjson(&b"{\"title\":\"playlist\"}\n{\"type\":\"key\",\"key\":\"invalid\"}\n"[..]),
+            Err(Error::Validation(PlaylistError::InvalidBeatmap {
+                idx: 0,
+                ..
+            }))
+        ));
+    }
+}
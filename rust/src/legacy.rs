@@ -0,0 +1,230 @@
+use crate::{beatmap::BeatmapType, playlist::PlaylistCoverType, Beatmap, Error, Playlist};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const PNG_B64_PREFIX: &str = "data:image/png;base64,";
+const JPG_B64_PREFIX: &str = "data:image/jpg;base64,";
+const JPEG_B64_PREFIX: &str = "data:image/jpeg;base64,";
+const WEBP_B64_PREFIX: &str = "data:image/webp;base64,";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LegacyPlaylist {
+    #[serde(rename = "playlistTitle")]
+    pub title: String,
+    #[serde(rename = "playlistAuthor", skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(
+        rename = "playlistDescription",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    #[serde(rename = "songs", default)]
+    pub maps: Vec<LegacyBeatmap>,
+    #[serde(rename = "image", skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+
+    #[serde(flatten, default)]
+    pub custom_data: IndexMap<String, Value>,
+}
+
+impl LegacyPlaylist {
+    pub fn from_playlist(playlist: &Playlist) -> Self {
+        let cover = playlist.cover.as_ref().and_then(|c| {
+            let prefix = match c.ty {
+                PlaylistCoverType::Png => PNG_B64_PREFIX,
+                PlaylistCoverType::Jpg => JPEG_B64_PREFIX,
+                PlaylistCoverType::Webp => WEBP_B64_PREFIX,
+                PlaylistCoverType::Unknown => return None,
+            };
+            Some(format!("{}{}", prefix, base64::encode(&c.data)))
+        });
+
+        Self {
+            title: playlist.title.clone(),
+            author: playlist.author.clone(),
+            description: playlist.description.clone(),
+            maps: playlist
+                .maps
+                .iter()
+                .filter_map(LegacyBeatmap::from_beatmap)
+                .collect(),
+            cover,
+            custom_data: playlist.custom_data.clone(),
+        }
+    }
+
+    pub fn into_playlist(self, preserve_custom_data: bool) -> Result<Playlist, Error> {
+        let Self {
+            title,
+            author,
+            description,
+            maps,
+            cover,
+            custom_data,
+        } = self;
+
+        let mut playlist = Playlist {
+            author,
+            description,
+            cover: None,
+            maps: maps
+                .into_iter()
+                .map(|m| m.into_beatmap(preserve_custom_data))
+                .collect(),
+            custom_data: if preserve_custom_data {
+                custom_data
+            } else {
+                IndexMap::new()
+            },
+            ..Playlist::new(title)
+        };
+        if let Some(c) = cover {
+            if let Some(b64) = c.strip_prefix(PNG_B64_PREFIX) {
+                let b64 = b64.trim_start_matches(' ');
+                let data = base64::decode(b64)?;
+                playlist.set_png_cover(data.as_slice())?;
+            } else if let Some(b64) = c.strip_prefix(JPG_B64_PREFIX) {
+                let b64 = b64.trim_start_matches(' ');
+                let data = base64::decode(b64)?;
+                playlist.set_jpg_cover(data.as_slice())?;
+            } else if let Some(b64) = c.strip_prefix(JPEG_B64_PREFIX) {
+                let b64 = b64.trim_start_matches(' ');
+                let data = base64::decode(b64)?;
+                playlist.set_jpg_cover(data.as_slice())?;
+            } else if let Some(b64) = c.strip_prefix(WEBP_B64_PREFIX) {
+                let b64 = b64.trim_start_matches(' ');
+                let data = base64::decode(b64)?;
+                playlist.set_webp_cover(data.as_slice())?;
+            }
+        }
+        Ok(playlist)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LegacyBeatmap {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(rename = "dateAdded", skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateTime<Utc>>,
+
+    #[serde(flatten, default)]
+    pub custom_data: IndexMap<String, Value>,
+}
+
+impl LegacyBeatmap {
+    /// Returns `None` for level ID beatmaps, which the legacy format can't represent
+    pub fn from_beatmap(beatmap: &Beatmap) -> Option<Self> {
+        if beatmap.ty == BeatmapType::LevelId {
+            log::warn!(
+                "skipping beatmap with level ID {:?} which can't be represented in the legacy format",
+                beatmap.level_id,
+            );
+            return None;
+        }
+
+        Some(Self {
+            key: beatmap.key.clone(),
+            hash: beatmap.hash.clone(),
+            date: beatmap.date,
+            custom_data: beatmap.custom_data.clone(),
+        })
+    }
+
+    pub fn into_beatmap(self, preserve_custom_data: bool) -> Beatmap {
+        let Self {
+            key,
+            hash,
+            date,
+            custom_data,
+        } = self;
+
+        // Beatmaps can only have the identifier matching their type
+        let (ty, key, hash) = if key.is_some() {
+            (BeatmapType::Key, key, None)
+        } else if hash.is_some() {
+            (BeatmapType::Hash, None, hash)
+        } else {
+            (BeatmapType::LevelId, None, None)
+        };
+
+        Beatmap {
+            ty,
+            date,
+            difficulties: Vec::new(),
+            key,
+            hash,
+            level_id: None,
+            custom_data: if preserve_custom_data {
+                custom_data
+            } else {
+                IndexMap::new()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        beatmap::BeatmapType,
+        legacy::LegacyPlaylist,
+        utils::{JPG_MAGIC_NUMBER, PNG_MAGIC_NUMBER},
+        Beatmap, Playlist,
+    };
+
+    fn round_trip(playlist: &Playlist) -> Playlist {
+        let mut json = Vec::new();
+        playlist.to_legacy_json(&mut json).unwrap();
+        let legacy: LegacyPlaylist = serde_json::from_slice(&json).unwrap();
+        legacy.into_playlist(true).unwrap()
+    }
+
+    #[test]
+    fn covers() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        let legacy = LegacyPlaylist::from_playlist(&playlist);
+        assert!(legacy.cover.unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(round_trip(&playlist).cover, playlist.cover);
+
+        playlist.set_jpg_cover(JPG_MAGIC_NUMBER.as_ref()).unwrap();
+        assert_eq!(round_trip(&playlist).cover, playlist.cover);
+
+        playlist
+            .set_webp_cover(b"RIFF\x04\0\0\0WEBP".as_ref())
+            .unwrap();
+        assert_eq!(round_trip(&playlist).cover, playlist.cover);
+    }
+
+    #[test]
+    fn maps() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        playlist.maps.push(Beatmap::new_hash(
+            "0123456789abcdef0123456789abcdef01234567".to_owned(),
+        ));
+        playlist
+            .maps
+            .push(Beatmap::new_level_id("level ID".to_owned()));
+
+        let new = round_trip(&playlist);
+        assert_eq!(new.maps, playlist.maps[..2]);
+    }
+
+    #[test]
+    fn key_and_hash() {
+        let json = br#"{"playlistTitle": "playlist", "songs": [
+            {"key": "16af", "hash": "0123456789abcdef0123456789abcdef01234567"}
+        ]}"#;
+        let legacy: LegacyPlaylist = serde_json::from_slice(json).unwrap();
+        let playlist = legacy.into_playlist(true).unwrap();
+        assert_eq!(playlist.maps[0].ty, BeatmapType::Key);
+        assert_eq!(playlist.maps[0].hash, None);
+        assert!(playlist.validate().is_ok());
+    }
+}
@@ -1,6 +1,11 @@
 pub mod beatmap;
 pub mod error;
+pub mod formats;
+#[cfg(feature = "legacy")]
+pub mod legacy;
 pub mod playlist;
+pub mod prelude;
+pub mod serde_zip_base64;
 mod utils;
 pub mod validation;
 
@@ -1,16 +1,34 @@
 use crate::{
-    beatmap::Beatmap,
+    beatmap::{Beatmap, BeatmapCharacteristic, BeatmapIdentifier, BeatmapType},
     error::Error,
-    utils::{self, JPG_MAGIC_NUMBER, JPG_MAGIC_NUMBER_LEN, PNG_MAGIC_NUMBER, PNG_MAGIC_NUMBER_LEN},
-    validation::{PlaylistCoverError, PlaylistError},
+    utils::{
+        self, JPG_MAGIC_NUMBER, JPG_MAGIC_NUMBER_LEN, PNG_MAGIC_NUMBER, PNG_MAGIC_NUMBER_LEN,
+        WEBP_MAGIC_NUMBER_LEN,
+    },
+    validation::{BeatmapError, PlaylistCoverError, PlaylistError, Validator},
 };
-use serde::{Deserialize, Serialize};
-use serde_json::{Map, Value};
+use chrono::{DateTime, Datelike, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use std::{
-    io::{Read, Seek, Write},
-    path::PathBuf,
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    convert::TryFrom,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, Cursor, Read, Seek, Write},
+    iter, mem,
+    ops::RangeBounds,
+    path::{Path, PathBuf},
+    slice, vec,
+};
+use zip::{
+    read::ZipFile,
+    result::ZipError,
+    write::{FileOptions, SimpleFileOptions},
+    CompressionMethod, ZipArchive, ZipWriter,
 };
-use zip::{ZipArchive, ZipWriter};
 
 pub const SCHEMA: &str =
     "https://raw.githubusercontent.com/raftario/blist/master/playlist.schema.json";
@@ -19,302 +37,3169 @@ fn schema() -> &'static str {
     SCHEMA
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+fn zip_entry<'a, R: Read + Seek>(
+    zip: &'a mut ZipArchive<R>,
+    name: &str,
+    password: Option<&[u8]>,
+) -> Result<ZipFile<'a>, Error> {
+    match password {
+        Some(p) => match zip.by_name_decrypt(name, p) {
+            Err(ZipError::InvalidPassword) => Err(Error::InvalidPassword),
+            r => Ok(r?),
+        },
+        None => Ok(zip.by_name(name)?),
+    }
+}
+
+// Undated maps go last in both directions
+fn date_key(map: &Beatmap) -> (bool, Option<DateTime<Utc>>) {
+    (map.date.is_none(), map.date)
+}
+fn date_key_desc(map: &Beatmap) -> (bool, Reverse<Option<DateTime<Utc>>>) {
+    (map.date.is_none(), Reverse(map.date))
+}
+
+fn file_options(options: PlaylistWriteOptions) -> SimpleFileOptions {
+    // A fixed timestamp keeps the output identical for identical playlists
+    SimpleFileOptions::default()
+        .compression_method(options.compression)
+        .compression_level(options.compression_level.map(i64::from))
+        .last_modified_time(zip::DateTime::default())
+}
+
+// Archives with duplicate names are ambiguous, since other readers might pick another entry
+fn check_duplicate_entries<R: Read + Seek>(reader: &mut R) -> Result<(), Error> {
+    let mut names = HashSet::new();
+    for name in utils::zip_entry_names(reader)? {
+        let name = String::from_utf8_lossy(&name).into_owned();
+        if names.contains(&name) {
+            return Err(Error::DuplicateZipEntry { name });
+        }
+        names.insert(name);
+    }
+    Ok(())
+}
+
+fn map_matches(map: &Beatmap, identifier: &BeatmapIdentifier<'_>) -> bool {
+    match (map.identifier(), identifier) {
+        (Some(BeatmapIdentifier::Key(a)), BeatmapIdentifier::Key(b))
+        | (Some(BeatmapIdentifier::Hash(a)), BeatmapIdentifier::Hash(b)) => {
+            a.eq_ignore_ascii_case(b)
+        }
+        (Some(BeatmapIdentifier::LevelId(a)), BeatmapIdentifier::LevelId(b)) => a == *b,
+        _ => false,
+    }
+}
+
+fn read_cover<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    c: &mut PlaylistCover,
+    password: Option<&[u8]>,
+) -> Result<(), Error> {
+    if utils::path_has_traversal(&c.path) {
+        return Err(Error::Validation(
+            PlaylistCoverError::InvalidCoverPath {
+                ty: "unknown",
+                path: c.path.clone(),
+            }
+            .into(),
+        ));
+    }
+    if !utils::path_is_invalid(&c.path) {
+        let ext = c.path.extension().unwrap();
+        if ext == "png" {
+            let mut cover_file = zip_entry(zip, c.path.to_str().unwrap(), password)?;
+
+            let mut magic_number = [0; PNG_MAGIC_NUMBER_LEN];
+            cover_file.read_exact(&mut magic_number)?;
+            if !constant_time_eq::constant_time_eq(
+                &magic_number[..PNG_MAGIC_NUMBER_LEN],
+                PNG_MAGIC_NUMBER,
+            ) {
+                return Err(Error::Validation(
+                    PlaylistCoverError::InvalidCoverData { ty: "png" }.into(),
+                ));
+            }
+
+            c.data.extend_from_slice(&magic_number);
+            cover_file.read_to_end(&mut c.data)?;
+            c.ty = PlaylistCoverType::Png;
+        } else if ext == "jpg" || ext == "jpeg" {
+            let mut cover_file = zip_entry(zip, c.path.to_str().unwrap(), password)?;
+
+            let mut magic_number = [0; JPG_MAGIC_NUMBER_LEN];
+            cover_file.read_exact(&mut magic_number)?;
+            if !constant_time_eq::constant_time_eq(
+                &magic_number[..JPG_MAGIC_NUMBER_LEN],
+                JPG_MAGIC_NUMBER,
+            ) {
+                return Err(Error::Validation(
+                    PlaylistCoverError::InvalidCoverData { ty: "jpg" }.into(),
+                ));
+            }
+
+            c.data.extend_from_slice(&magic_number);
+            cover_file.read_to_end(&mut c.data)?;
+            c.ty = PlaylistCoverType::Jpg;
+        } else if ext == "webp" {
+            let mut cover_file = zip_entry(zip, c.path.to_str().unwrap(), password)?;
+
+            let mut magic_number = [0; WEBP_MAGIC_NUMBER_LEN];
+            cover_file.read_exact(&mut magic_number)?;
+            if !utils::webp_magic_number_matches(&magic_number) {
+                return Err(Error::Validation(
+                    PlaylistCoverError::InvalidCoverData { ty: "webp" }.into(),
+                ));
+            }
+
+            c.data.extend_from_slice(&magic_number);
+            cover_file.read_to_end(&mut c.data)?;
+            c.ty = PlaylistCoverType::Webp;
+        } else {
+            return Err(Error::Validation(
+                PlaylistCoverError::UnknownCoverType.into(),
+            ));
+        }
+    } else {
+        return Err(Error::Validation(
+            PlaylistCoverError::InvalidCoverPath {
+                ty: "unknown",
+                path: c.path.clone(),
+            }
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Playlist {
-    #[serde(rename = "$schema", default = "schema")]
+    #[serde(rename = "$schema", skip_deserializing, default = "schema")]
     pub _schema: &'static str,
+    /// The `$schema` URL found when reading the playlist, only used by
+    /// [`Playlist::schema_is_stale`]
+    #[serde(rename = "$schema", default, skip_serializing)]
+    pub original_schema: Option<String>,
     pub title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
     #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
     pub cover: Option<PlaylistCover>,
+    #[serde(
+        flatten,
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "CoverSet::deserialize_optional"
+    )]
+    pub cover_set: Option<CoverSet>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map_order: Option<MapOrder>,
     pub maps: Vec<Beatmap>,
-    #[serde(default, skip_serializing_if = "Map::is_empty")]
-    pub custom_data: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub custom_data: IndexMap<String, Value>,
+    #[serde(skip)]
+    pub validator: Validator,
+}
+
+// The validator isn't part of the playlist content so it's ignored
+impl PartialEq for Playlist {
+    fn eq(&self, other: &Self) -> bool {
+        self._schema == other._schema
+            && self.title == other.title
+            && self.author == other.author
+            && self.description == other.description
+            && self.tags == other.tags
+            && self.cover == other.cover
+            && self.cover_set == other.cover_set
+            && self.map_order == other.map_order
+            && self.maps == other.maps
+            && self.custom_data == other.custom_data
+    }
 }
 
 impl Playlist {
     pub fn new(title: String) -> Self {
         Self {
-            _schema: SCHEMA,
+            _schema: schema(),
+            original_schema: None,
             title,
             author: None,
             description: None,
+            tags: None,
             cover: None,
+            cover_set: None,
+            map_order: None,
             maps: Vec::new(),
-            custom_data: Map::new(),
+            custom_data: IndexMap::new(),
+            validator: Validator::default(),
+        }
+    }
+    #[inline]
+    pub fn new_with_maps(title: String, maps: Vec<Beatmap>) -> Self {
+        Self {
+            maps,
+            ..Self::new(title)
+        }
+    }
+
+    #[inline]
+    pub fn with_author<S: Into<String>>(mut self, author: S) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+    pub fn with_description<S: Into<String>>(
+        mut self,
+        description: S,
+    ) -> Result<Self, PlaylistError> {
+        let description = description.into();
+        if description.is_empty() {
+            return Err(PlaylistError::InvalidField {
+                field: "description",
+                value: description,
+            });
+        }
+        self.description = Some(description);
+        Ok(self)
+    }
+    #[inline]
+    pub fn with_custom_data_entry<S: Into<String>, T: Serialize>(
+        mut self,
+        key: S,
+        value: T,
+    ) -> Result<Self, serde_json::Error> {
+        self.custom_data
+            .insert(key.into(), serde_json::to_value(value)?);
+        Ok(self)
+    }
+    /// Adds `tag` unless the playlist already has it
+    pub fn add_tag(&mut self, tag: String) {
+        if !self.has_tag(&tag) {
+            self.tags.get_or_insert_with(Vec::new).push(tag);
+        }
+    }
+    pub fn remove_tag(&mut self, tag: &str) {
+        if let Some(tags) = &mut self.tags {
+            tags.retain(|t| t != tag);
+            if tags.is_empty() {
+                self.tags = None;
+            }
         }
     }
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().flatten().any(|t| t == tag)
+    }
+    #[inline]
+    pub fn has_any_tag(&self, tags: &[&str]) -> bool {
+        tags.iter().any(|t| self.has_tag(t))
+    }
+    /// Tags apply to the whole playlist, maps don't have their own
+    #[inline]
+    pub fn has_all_tags(&self, tags: &[&str]) -> bool {
+        tags.iter().all(|t| self.has_tag(t))
+    }
+    #[inline]
+    pub fn filter_playlists_by_tag<'a>(playlists: &'a [Playlist], tag: &str) -> Vec<&'a Playlist> {
+        playlists.iter().filter(|p| p.has_tag(tag)).collect()
+    }
+    #[inline]
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.validator = validator;
+        self
+    }
+    /// Finishes a builder chain by validating the playlist
+    #[inline]
+    pub fn build(self) -> Result<Self, Error> {
+        self.validate()?;
+        Ok(self)
+    }
 
+    /// Reads a playlist, validating it with the default validator.
+    ///
+    /// Map identifiers not matching the map type, which older versions of the converter produced,
+    /// are dropped instead of failing validation.
+    #[inline]
     pub fn read<R: Read + Seek>(reader: R) -> Result<Self, Error> {
+        Self::read_inner(reader, None, ReadOptions::default())
+    }
+    #[inline]
+    pub fn read_with_options<R: Read + Seek>(
+        reader: R,
+        options: ReadOptions,
+    ) -> Result<Self, Error> {
+        Self::read_inner(reader, None, options)
+    }
+    #[inline]
+    pub fn decrypt_read<R: Read + Seek>(reader: R, password: &str) -> Result<Self, Error> {
+        Self::read_inner(reader, Some(password.as_bytes()), ReadOptions::default())
+    }
+    fn read_inner<R: Read + Seek>(
+        mut reader: R,
+        password: Option<&[u8]>,
+        options: ReadOptions,
+    ) -> Result<Self, Error> {
+        check_duplicate_entries(&mut reader)?;
         let mut zip = ZipArchive::new(reader)?;
 
         let mut playlist: Self = {
-            let mut playlist_file = zip.by_name("playlist.json")?;
-            serde_json::from_reader(&mut playlist_file)?
+            // Parsing from a string gives more accurate error positions than parsing from a reader
+            let mut json = String::new();
+            zip_entry(&mut zip, "playlist.json", password)?.read_to_string(&mut json)?;
+            // Some Windows editors save JSON with a byte order mark
+            let json = json.strip_prefix('\u{feff}').unwrap_or(&json);
+            if options.infer_missing_type {
+                let mut value: Value = serde_json::from_str(json)?;
+                if let Some(Value::Array(maps)) = value.get_mut("maps") {
+                    for m in maps {
+                        if let Value::Object(m) = m {
+                            Beatmap::infer_missing_type(m);
+                        }
+                    }
+                }
+                serde_json::from_value(value)?
+            } else {
+                serde_json::from_str(json)?
+            }
         };
 
         if let Some(c) = &mut playlist.cover {
-            if !utils::path_is_invalid(&c.path) {
-                let ext = c.path.extension().unwrap();
-                if ext == "png" {
-                    let mut cover_file = zip.by_name(c.path.to_str().unwrap())?;
-
-                    let mut magic_number = [0; PNG_MAGIC_NUMBER_LEN];
-                    cover_file.read_exact(&mut magic_number)?;
-                    if !constant_time_eq::constant_time_eq(
-                        &magic_number[..PNG_MAGIC_NUMBER_LEN],
-                        PNG_MAGIC_NUMBER,
-                    ) {
-                        return Err(Error::Validation(
-                            PlaylistCoverError::InvalidCoverData { ty: "png" }.into(),
-                        ));
+            read_cover(&mut zip, c, password)?;
+        }
+        if let Some(cs) = &mut playlist.cover_set {
+            for (field, c) in cs.entries_mut() {
+                read_cover(&mut zip, c, password).map_err(|e| match e {
+                    Error::Validation(PlaylistError::InvalidCover(error)) => {
+                        PlaylistError::InvalidCoverSet { field, error }.into()
                     }
+                    e => e,
+                })?;
+            }
+        }
+
+        playlist.drop_extra_identifiers();
+        playlist.validate_inner(false)?;
+        Ok(playlist)
+    }
+    #[inline]
+    pub fn read_from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+    /// Lists the names of the entries in a playlist archive, in order, without parsing them
+    pub fn zip_entries<R: Read + Seek>(reader: R) -> Result<Vec<String>, Error> {
+        let mut zip = ZipArchive::new(reader)?;
+        (0..zip.len())
+            .map(|i| Ok(zip.by_index_raw(i)?.name().to_owned()))
+            .collect()
+    }
+    /// Reads only the cover from a playlist archive, skipping over the maps
+    pub fn read_cover_only<R: Read + Seek>(mut reader: R) -> Result<Option<PlaylistCover>, Error> {
+        #[derive(Deserialize)]
+        struct CoverPath {
+            #[serde(rename = "cover", default)]
+            path: Option<PathBuf>,
+        }
 
-                    cover_file.read_to_end(&mut c.data)?;
-                    c.ty = PlaylistCoverType::Png;
-                } else if ext == "jpg" || ext == "jpeg" {
-                    let mut cover_file = zip.by_name(c.path.to_str().unwrap())?;
+        check_duplicate_entries(&mut reader)?;
+        let mut zip = ZipArchive::new(reader)?;
+        let mut json = String::new();
+        zip_entry(&mut zip, "playlist.json", None)?.read_to_string(&mut json)?;
+        let json = json.strip_prefix('\u{feff}').unwrap_or(&json);
+        let path = match serde_json::from_str::<CoverPath>(json)?.path {
+            Some(p) => p,
+            None => return Ok(None),
+        };
 
-                    let mut magic_number = [0; JPG_MAGIC_NUMBER_LEN];
-                    cover_file.read_exact(&mut magic_number)?;
-                    if !constant_time_eq::constant_time_eq(
-                        &magic_number[..JPG_MAGIC_NUMBER_LEN],
-                        JPG_MAGIC_NUMBER,
-                    ) {
-                        return Err(Error::Validation(
-                            PlaylistCoverError::InvalidCoverData { ty: "jpg" }.into(),
-                        ));
-                    }
+        let mut cover = PlaylistCover {
+            path,
+            data: Vec::new(),
+            ty: PlaylistCoverType::Unknown,
+        };
+        read_cover(&mut zip, &mut cover, None)?;
+        Ok(Some(cover))
+    }
+    /// Reads a playlist by memory-mapping the file instead of reading it into a buffer.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or another one, until this
+    /// returns. Doing so is undefined behaviour and can crash the process with `SIGBUS`.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn read_mmap<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees the file isn't modified while mapped, and the mapping is
+        // dropped before returning so no reference to it outlives the call
+        let mmap = unsafe { memmap2::MmapOptions::new().map(&file)? };
+        Self::from_bytes(&mmap)
+    }
 
-                    cover_file.read_to_end(&mut c.data)?;
-                    c.ty = PlaylistCoverType::Jpg;
-                } else {
-                    return Err(Error::Validation(
-                        PlaylistCoverError::UnknownCoverType.into(),
-                    ));
+    /// Reads a playlist from an async reader.
+    ///
+    /// `zip` doesn't support async IO, so the whole archive is buffered in memory before being
+    /// parsed.
+    #[cfg(feature = "tokio")]
+    pub async fn read_async<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        use tokio::io::AsyncReadExt;
+
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await?;
+        Self::from_bytes(&buffer)
+    }
+    #[cfg(feature = "tokio")]
+    pub async fn read_from_path_async<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::read_async(tokio::fs::File::open(path).await?).await
+    }
+
+    /// Validates the playlist and writes it as a ZIP archive.
+    ///
+    /// The archive is built synchronously on the calling thread and the writer is only borrowed
+    /// for the duration of the call, so it doesn't need to be `Send`. `Playlist` itself is
+    /// `Send + Sync`, which means writing from another thread only requires moving (or sharing)
+    /// the playlist there and creating the writer on that thread.
+    #[inline]
+    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<(), Error> {
+        self.write_with_options(writer, PlaylistWriteOptions::default())
+    }
+    pub fn write_with_options<W: Write + Seek>(
+        &self,
+        writer: W,
+        options: PlaylistWriteOptions,
+    ) -> Result<(), Error> {
+        self.validate_inner(true)?;
+
+        let mut zip = ZipWriter::new(writer);
+        self.write_entries(&mut zip, file_options(options))?;
+        zip.finish()?;
+        Ok(())
+    }
+    /// Writes the playlist with every entry encrypted using AES-256.
+    ///
+    /// The result can be read back with [`Playlist::decrypt_read`].
+    #[cfg(feature = "aes-crypto")]
+    pub fn encrypt_write<W: Write + Seek>(&self, writer: W, password: &str) -> Result<(), Error> {
+        self.validate_inner(true)?;
+
+        let options = file_options(PlaylistWriteOptions::default())
+            .with_aes_encryption(zip::AesMode::Aes256, password);
+        let mut zip = ZipWriter::new(writer);
+        self.write_entries(&mut zip, options)?;
+        zip.finish()?;
+        Ok(())
+    }
+    /// Appends the playlist to an existing ZIP archive, leaving its other entries untouched.
+    ///
+    /// Existing entries can't be replaced, so this fails with [`Error::DuplicateZipEntry`] if the
+    /// archive already contains a `playlist.json` or an entry named like one of the covers.
+    pub fn append_to<W: Read + Write + Seek>(&self, mut writer: W) -> Result<(), Error> {
+        self.validate_inner(true)?;
+
+        {
+            let zip = ZipArchive::new(&mut writer)?;
+            let existing: HashSet<&str> = zip.file_names().collect();
+            let covers = self.covers().map(|c| c.path.to_string_lossy());
+            for name in iter::once("playlist.json".into()).chain(covers) {
+                if existing.contains(name.as_ref()) {
+                    return Err(Error::DuplicateZipEntry {
+                        name: name.into_owned(),
+                    });
+                }
+            }
+        }
+
+        let mut zip = ZipWriter::new_append(writer)?;
+        self.write_entries(&mut zip, file_options(PlaylistWriteOptions::default()))?;
+        zip.finish()?;
+        Ok(())
+    }
+    fn covers(&self) -> impl Iterator<Item = &PlaylistCover> {
+        let cover_set_entries = self.cover_set.iter().flat_map(CoverSet::entries);
+        self.cover.iter().chain(cover_set_entries.map(|(_, c)| c))
+    }
+    fn write_entries<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        options: FileOptions<'_, ()>,
+    ) -> Result<(), Error> {
+        zip.start_file("playlist.json", options)?;
+        serde_json::to_writer(&mut *zip, &self)?;
+
+        for c in self.covers() {
+            zip.start_file(c.path.to_string_lossy(), options)?;
+            zip.write_all(&c.data)?;
+        }
+        Ok(())
+    }
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.validate_inner(true)?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+    /// Writes the playlist to an async writer.
+    ///
+    /// `zip` doesn't support async IO, so the whole archive is built in memory before being
+    /// written.
+    #[cfg(feature = "tokio")]
+    pub async fn write_async<W>(&self, mut writer: W) -> Result<(), Error>
+    where
+        W: tokio::io::AsyncWrite + tokio::io::AsyncSeek + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+
+        let buffer = self.to_bytes()?;
+        writer.write_all(&buffer).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+    #[cfg(feature = "tokio")]
+    pub async fn write_to_path_async<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        use tokio::io::AsyncWriteExt;
+
+        // Building the archive first validates it before the file gets truncated
+        let buffer = self.to_bytes()?;
+        let mut file = tokio::fs::File::create(path).await?;
+        file.write_all(&buffer).await?;
+        file.flush().await?;
+        Ok(())
+    }
+    /// Overwrites the existing playlist at `path` without going through a temporary file, keeping
+    /// the archive entries that don't belong to the old or new playlist.
+    ///
+    /// The playlist is validated before the file is truncated, but unlike
+    /// [`Playlist::write_atomic`] a crash while writing can leave a corrupted file behind.
+    pub fn update_in_place<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.validate_inner(true)?;
+
+        let path = path.as_ref();
+        let mut existing = ZipArchive::new(Cursor::new(fs::read(path)?))?;
+        let mut replaced: HashSet<String> = self
+            .covers()
+            .map(|c| c.path.to_string_lossy().into_owned())
+            .collect();
+        replaced.insert("playlist.json".to_owned());
+        if let Ok(json) = existing.by_name("playlist.json") {
+            let old: Value = serde_json::from_reader(json)?;
+            for field in &["cover", "coverSmall", "coverLarge"] {
+                if let Some(Value::String(p)) = old.get(field) {
+                    replaced.insert(p.clone());
                 }
-            } else {
-                return Err(Error::Validation(
-                    PlaylistCoverError::InvalidCoverPath {
-                        ty: "unknown",
-                        path: c.path.clone(),
-                    }
-                    .into(),
-                ));
             }
         }
 
+        let file = OpenOptions::new().write(true).truncate(true).open(path)?;
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+        for i in 0..existing.len() {
+            let entry = existing.by_index_raw(i)?;
+            if !replaced.contains(entry.name()) {
+                zip.raw_copy_file(entry)?;
+            }
+        }
+        self.write_entries(&mut zip, file_options(PlaylistWriteOptions::default()))?;
+        zip.finish()?.flush()?;
+        Ok(())
+    }
+    /// Writes the playlist in the legacy JSON format, skipping level ID beatmaps
+    #[cfg(feature = "legacy")]
+    pub fn to_legacy_json<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let legacy = crate::legacy::LegacyPlaylist::from_playlist(self);
+        serde_json::to_writer(writer, &legacy)?;
+        Ok(())
+    }
+    #[inline]
+    pub fn write_to_path_atomic<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.write_atomic(path)
+    }
+    /// Writes the playlist to a temporary file next to `path` and only renames it to `path` once
+    /// it has been fully written, so that an existing playlist is never left half-written.
+    pub fn write_atomic<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        self.validate_inner(true)?;
+
+        let tmp_path = path.with_extension("blist.tmp");
+        let write_tmp = || -> Result<(), Error> {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            self.write(&mut writer)?;
+            writer
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .sync_all()?;
+            Ok(())
+        };
+        if let Err(e) = write_tmp() {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        // `fs::rename` replaces the destination, using `MoveFileExW` with
+        // `MOVEFILE_REPLACE_EXISTING` on Windows
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::read(Cursor::new(bytes))
+    }
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buffer = Cursor::new(Vec::new());
+        self.write(&mut buffer)?;
+        Ok(buffer.into_inner())
+    }
+
+    #[inline]
+    pub fn read_from_base64(encoded: &str) -> Result<Self, Error> {
+        Self::from_bytes(&base64::decode(encoded)?)
+    }
+    #[inline]
+    pub fn to_base64(&self) -> Result<String, Error> {
+        Ok(base64::encode(self.to_bytes()?))
+    }
+
+    /// Reads only the `playlist.json` content, without the ZIP archive.
+    ///
+    /// Cover data isn't available in this case, so covers are left empty with an `Unknown` type
+    /// and only the metadata is validated.
+    pub fn read_json<R: Read>(reader: R) -> Result<Self, Error> {
+        let mut playlist: Self = serde_json::from_reader(reader)?;
+        playlist.drop_extra_identifiers();
+        playlist.validate_inner(false)?;
+        Ok(playlist)
+    }
+    /// Writes only the `playlist.json` content, without the ZIP archive or cover data
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), Error> {
+        self.validate_inner(false)?;
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+    /// Converts the playlist to a JSON value, which only contains the cover paths and not their
+    /// data
+    #[inline]
+    pub fn to_json_value(&self) -> Result<Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+    /// Converts a JSON value to a playlist, with covers left empty like in [`Playlist::read_json`]
+    pub fn from_json_value(value: Value) -> Result<Self, Error> {
+        let mut playlist: Self = serde_json::from_value(value)?;
+        playlist.drop_extra_identifiers();
         playlist.validate_inner(false)?;
         Ok(playlist)
     }
-    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<(), Error> {
-        self.validate_inner(true)?;
+    /// Inserts the entries of a JSON object into the custom data, keeping existing keys as is
+    pub fn merge_custom_data_from_value(&mut self, value: &serde_json::Map<String, Value>) {
+        for (k, v) in value {
+            self.custom_data
+                .entry(k.clone())
+                .or_insert_with(|| v.clone());
+        }
+    }
+
+    /// Sets the cover data as is, which is only checked when writing the playlist
+    pub fn set_cover_raw(
+        &mut self,
+        data: Vec<u8>,
+        ty: PlaylistCoverType,
+    ) -> Result<(), PlaylistCoverError> {
+        let path = PathBuf::from(match ty {
+            PlaylistCoverType::Png => "cover.png",
+            PlaylistCoverType::Jpg => "cover.jpg",
+            PlaylistCoverType::Webp => "cover.webp",
+            PlaylistCoverType::Unknown => return Err(PlaylistCoverError::UnknownCoverType),
+        });
+
+        if let Some(c) = self.cover.as_mut() {
+            c.path = path;
+            c.data = data;
+            c.ty = ty;
+        } else {
+            self.cover = Some(PlaylistCover { path, data, ty });
+        }
+
+        Ok(())
+    }
+    pub fn set_png_cover<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+        let path = PathBuf::from("cover.png");
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let ty = PlaylistCoverType::Png;
+
+        if let Some(c) = self.cover.as_mut() {
+            c.path = path;
+            c.data = data;
+            c.ty = ty;
+        } else {
+            self.cover = Some(PlaylistCover { path, data, ty });
+        }
+
+        Ok(())
+    }
+    pub fn set_jpg_cover<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+        let path = PathBuf::from("cover.jpg");
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let ty = PlaylistCoverType::Jpg;
+
+        if let Some(c) = self.cover.as_mut() {
+            c.path = path;
+            c.data = data;
+            c.ty = ty;
+        } else {
+            self.cover = Some(PlaylistCover { path, data, ty });
+        }
+
+        Ok(())
+    }
+    pub fn set_webp_cover<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+        let path = PathBuf::from("cover.webp");
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let ty = PlaylistCoverType::Webp;
+
+        if let Some(c) = self.cover.as_mut() {
+            c.path = path;
+            c.data = data;
+            c.ty = ty;
+        } else {
+            self.cover = Some(PlaylistCover { path, data, ty });
+        }
+
+        Ok(())
+    }
+    #[inline]
+    pub fn swap_cover(&mut self, other: &mut Playlist) {
+        mem::swap(&mut self.cover, &mut other.cover);
+    }
+    #[inline]
+    pub fn cover_data_mut(&mut self) -> Option<&mut Vec<u8>> {
+        self.cover.as_mut().map(|c| &mut c.data)
+    }
+    #[inline]
+    pub fn thumbnail_cover(&self) -> Option<&PlaylistCover> {
+        match &self.cover_set {
+            Some(cs) => cs.thumbnail.as_ref(),
+            None => self.cover.as_ref(),
+        }
+    }
+    #[inline]
+    pub fn full_cover(&self) -> Option<&PlaylistCover> {
+        match &self.cover_set {
+            Some(cs) => cs.full.as_ref(),
+            None => self.cover.as_ref(),
+        }
+    }
+    /// Reads the cover dimensions from the image header without decoding it
+    pub fn cover_pixel_dimensions(&self) -> Option<(u32, u32)> {
+        let cover = self.cover.as_ref()?;
+        match cover.ty {
+            PlaylistCoverType::Png => utils::png_dimensions(&cover.data),
+            PlaylistCoverType::Jpg => utils::jpg_dimensions(&cover.data),
+            PlaylistCoverType::Webp => utils::webp_dimensions(&cover.data),
+            PlaylistCoverType::Unknown => None,
+        }
+    }
+    #[inline]
+    pub fn has_cover(&self) -> bool {
+        self.cover.is_some()
+    }
+    #[inline]
+    pub fn cover_type(&self) -> Option<&PlaylistCoverType> {
+        self.cover.as_ref().map(|c| &c.ty)
+    }
+    #[inline]
+    pub fn cover_data(&self) -> Option<&[u8]> {
+        self.cover.as_ref().map(|c| c.data.as_slice())
+    }
+    #[inline]
+    pub fn remove_cover(&mut self) {
+        self.cover = None;
+    }
+    #[inline]
+    pub fn cover_path(&self) -> Option<&Path> {
+        self.cover.as_ref().map(|c| c.path.as_path())
+    }
+
+    pub fn maps_with_date(&self) -> impl Iterator<Item = &Beatmap> {
+        self.maps.iter().filter(|m| m.date.is_some())
+    }
+    pub fn maps_with_no_date(&self) -> impl Iterator<Item = &Beatmap> {
+        self.maps.iter().filter(|m| m.date.is_none())
+    }
+    /// Collects the `Some` results of `f` over the maps.
+    ///
+    /// ```
+    /// # use blist::{Beatmap, Playlist};
+    /// let mut playlist = Playlist::new("playlist".to_owned());
+    /// playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+    /// playlist.maps.push(Beatmap::new_hash("0123456789abcdef0123456789abcdef01234567".to_owned()));
+    ///
+    /// let hashes = playlist.map_filter_maps(|m| m.hash.as_deref().map(str::to_owned));
+    /// assert_eq!(hashes, ["0123456789abcdef0123456789abcdef01234567"]);
+    /// ```
+    #[inline]
+    pub fn map_filter_maps<F, R>(&self, f: F) -> Vec<R>
+    where
+        F: Fn(&Beatmap) -> Option<R>,
+    {
+        self.maps.iter().filter_map(f).collect()
+    }
+    /// Returns the maps without difficulties, which show up in every game mode
+    #[inline]
+    pub fn maps_with_missing_difficulties(&self) -> impl Iterator<Item = (usize, &Beatmap)> {
+        self.maps
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.difficulties.is_empty())
+    }
+    #[inline]
+    pub fn has_maps_without_difficulties(&self) -> bool {
+        self.maps.iter().any(|m| m.difficulties.is_empty())
+    }
+    /// Groups maps by the `(year, month)` of their date, with undated maps under `(0, 0)`
+    pub fn maps_by_date_bucket(&self) -> BTreeMap<(i32, u32), Vec<&Beatmap>> {
+        let mut buckets: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for m in &self.maps {
+            let bucket = m.date.map_or((0, 0), |d| (d.year(), d.month()));
+            buckets.entry(bucket).or_default().push(m);
+        }
+        buckets
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+    pub fn stats(&self) -> PlaylistStats {
+        let count = |ty| self.maps.iter().filter(|m| m.ty == ty).count();
+        PlaylistStats {
+            key_count: count(BeatmapType::Key),
+            hash_count: count(BeatmapType::Hash),
+            level_id_count: count(BeatmapType::LevelId),
+            total: self.maps.len(),
+            has_cover: self.cover.is_some(),
+        }
+    }
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Beatmap> {
+        self.maps.iter()
+    }
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Beatmap> {
+        self.maps.iter_mut()
+    }
+    #[inline]
+    pub fn maps_iter_with_index(&self) -> impl Iterator<Item = (usize, &Beatmap)> {
+        self.maps.iter().enumerate()
+    }
+    #[inline]
+    pub fn maps_iter_mut_with_index(&mut self) -> impl Iterator<Item = (usize, &mut Beatmap)> {
+        self.maps.iter_mut().enumerate()
+    }
+    #[inline]
+    pub fn maps_at_range<R: RangeBounds<usize>>(&self, range: R) -> &[Beatmap] {
+        &self.maps[(range.start_bound().cloned(), range.end_bound().cloned())]
+    }
+
+    pub fn reorder_maps(&mut self, permutation: &[usize]) -> Result<(), Error> {
+        let len = self.maps.len();
+        let mut seen = vec![false; len];
+        for &i in permutation {
+            if i >= len || seen[i] {
+                break;
+            }
+            seen[i] = true;
+        }
+        if permutation.len() != len || seen.contains(&false) {
+            return Err(PlaylistError::InvalidPermutation {
+                permutation: permutation.to_vec(),
+                len,
+            }
+            .into());
+        }
+
+        let mut maps: Vec<Option<Beatmap>> = self.maps.drain(..).map(Some).collect();
+        self.maps = permutation
+            .iter()
+            .map(|&i| maps[i].take().unwrap())
+            .collect();
+        Ok(())
+    }
+
+    pub fn fill_missing_dates(&mut self, fallback: DateTime<Utc>) {
+        for m in self.maps.iter_mut().filter(|m| m.date.is_none()) {
+            m.date = Some(fallback);
+        }
+    }
+    #[inline]
+    pub fn fill_missing_dates_now(&mut self) {
+        self.fill_missing_dates(Utc::now())
+    }
+    pub fn clear_all_dates(&mut self) {
+        for m in &mut self.maps {
+            m.date = None;
+        }
+    }
+
+    pub fn sort_maps_by_key_numeric(&mut self) {
+        let (mut keys, others): (Vec<Beatmap>, Vec<Beatmap>) =
+            self.maps.drain(..).partition(|m| m.ty == BeatmapType::Key);
+        keys.sort_by_key(|m| {
+            let k = m.key_as_u64();
+            (k.is_none(), k)
+        });
+        self.maps = keys;
+        self.maps.extend(others);
+    }
+    /// Sorts maps from oldest to newest, with undated maps last
+    #[inline]
+    pub fn sort_maps_by_date(&mut self) {
+        self.maps.sort_by_key(date_key);
+    }
+    /// Sorts maps from newest to oldest, with undated maps last
+    #[inline]
+    pub fn sort_maps_by_date_desc(&mut self) {
+        self.maps.sort_by_key(date_key_desc);
+    }
+    #[inline]
+    pub fn sort_maps<F: FnMut(&Beatmap, &Beatmap) -> Ordering>(&mut self, compare: F) {
+        self.maps.sort_by(compare);
+    }
+
+    /// Sorts the maps by date like [`Playlist::sort_maps_by_date`]
+    #[inline]
+    pub fn with_sorted_maps(mut self) -> Self {
+        self.sort_maps_by_date();
+        self
+    }
+    /// Inserts the map after every map with an earlier or equal date, keeping undated maps last
+    pub fn insert_map_sorted(&mut self, map: Beatmap) -> Result<(), BeatmapError> {
+        map.validate()?;
+        let key = date_key(&map);
+        let idx = self.maps.partition_point(|m| date_key(m) <= key);
+        self.maps.insert(idx, map);
+        Ok(())
+    }
+
+    pub fn maps_sorted(&self) -> Vec<&Beatmap> {
+        let mut maps: Vec<&Beatmap> = self.maps.iter().collect();
+        match self.map_order {
+            Some(MapOrder::DateAscending) => maps.sort_by_key(|m| date_key(m)),
+            Some(MapOrder::DateDescending) => maps.sort_by_key(|m| date_key_desc(m)),
+            Some(MapOrder::Insertion) | Some(MapOrder::Custom) | None => (),
+        }
+        maps
+    }
+
+    #[inline]
+    pub fn maps_as_json_array(&self) -> Value {
+        Value::Array(self.maps.iter().map(Beatmap::to_json_object).collect())
+    }
+
+    /// Returns the normalized identifiers prefixed with their type, like `key:1a2b`
+    pub fn fingerprint_maps(&self) -> BTreeSet<String> {
+        self.maps
+            .iter()
+            .filter_map(|m| Some(format!("{}:{}", m.ty, m.normalized_identifier()?)))
+            .collect()
+    }
+
+    pub fn find_duplicates(&self) -> Vec<(usize, usize)> {
+        let mut first = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (idx, m) in self.maps.iter().enumerate() {
+            if let Some(id) = m.normalized_identifier() {
+                if let Some(&f) = first.get(&(m.ty, id.clone())) {
+                    duplicates.push((f, idx));
+                } else {
+                    first.insert((m.ty, id), idx);
+                }
+            }
+        }
+        duplicates.sort_unstable();
+        duplicates
+    }
+    #[inline]
+    pub fn sort_difficulty_lists(&mut self) {
+        self.maps.iter_mut().for_each(Beatmap::sort_difficulties);
+    }
+    #[inline]
+    pub fn normalize_all_hashes(&mut self) {
+        self.maps.iter_mut().for_each(Beatmap::normalize_hash);
+    }
+    pub fn has_duplicates(&self) -> bool {
+        let mut seen = HashSet::new();
+        self.maps
+            .iter()
+            .filter_map(|m| Some((m.ty, m.normalized_identifier()?)))
+            .any(|id| !seen.insert(id))
+    }
+    pub fn deduplicate_maps(&mut self) {
+        let mut seen = HashSet::new();
+        self.maps.retain(|m| match m.normalized_identifier() {
+            Some(id) => seen.insert((m.ty, id)),
+            None => true,
+        });
+    }
+
+    /// Hashes and keys are compared case-insensitively
+    #[inline]
+    pub fn find(&self, identifier: &BeatmapIdentifier<'_>) -> Option<&Beatmap> {
+        self.maps.iter().find(|m| map_matches(m, identifier))
+    }
+    #[inline]
+    pub fn find_mut(&mut self, identifier: &BeatmapIdentifier<'_>) -> Option<&mut Beatmap> {
+        self.maps.iter_mut().find(|m| map_matches(m, identifier))
+    }
+    #[inline]
+    pub fn find_by_hash(&self, hash: &str) -> Option<&Beatmap> {
+        self.find(&BeatmapIdentifier::Hash(hash))
+    }
+    #[inline]
+    pub fn find_by_hash_mut(&mut self, hash: &str) -> Option<&mut Beatmap> {
+        self.find_mut(&BeatmapIdentifier::Hash(hash))
+    }
+    #[inline]
+    pub fn find_by_key(&self, key: &str) -> Option<&Beatmap> {
+        self.find(&BeatmapIdentifier::Key(key))
+    }
+    #[inline]
+    pub fn find_by_key_mut(&mut self, key: &str) -> Option<&mut Beatmap> {
+        self.find_mut(&BeatmapIdentifier::Key(key))
+    }
+    #[inline]
+    pub fn find_by_level_id(&self, level_id: &str) -> Option<&Beatmap> {
+        self.find(&BeatmapIdentifier::LevelId(level_id))
+    }
+    #[inline]
+    pub fn find_by_level_id_mut(&mut self, level_id: &str) -> Option<&mut Beatmap> {
+        self.find_mut(&BeatmapIdentifier::LevelId(level_id))
+    }
+
+    /// Compares the maps of both playlists by type and identifier, ignoring everything else
+    #[inline]
+    pub fn diff<'a>(&'a self, other: &'a Playlist) -> PlaylistDiff<'a> {
+        PlaylistDiff {
+            added: other.maps_unique_to(self),
+            removed: self.maps_unique_to(other),
+        }
+    }
+    /// Returns the maps of `self` missing from `other`, which are the removed maps of the diff
+    pub fn maps_unique_to<'a>(&'a self, other: &Playlist) -> Vec<&'a Beatmap> {
+        let ids: HashSet<_> = other
+            .maps
+            .iter()
+            .map(|m| (m.ty, m.normalized_identifier()))
+            .collect();
+        self.maps
+            .iter()
+            .filter(|m| !ids.contains(&(m.ty, m.normalized_identifier())))
+            .collect()
+    }
+    pub fn diff_owned(self, other: Playlist) -> (Vec<Beatmap>, Vec<Beatmap>) {
+        let PlaylistDiff { added, removed } = self.diff(&other);
+        (
+            added.into_iter().cloned().collect(),
+            removed.into_iter().cloned().collect(),
+        )
+    }
+    /// Diffs against the playlist stored at `path`, returning the `(added, removed)` maps
+    pub fn compute_diff_from_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(Vec<Beatmap>, Vec<Beatmap>), Error> {
+        let loaded = Self::read_from_path(path)?;
+        let PlaylistDiff { added, removed } = self.diff(&loaded);
+        Ok((
+            added.into_iter().cloned().collect(),
+            removed.into_iter().cloned().collect(),
+        ))
+    }
+
+    /// Returns whether any map matches `f`, like [`Iterator::any`]
+    #[inline]
+    pub fn any_map<F: Fn(&Beatmap) -> bool>(&self, f: F) -> bool {
+        self.maps.iter().any(f)
+    }
+    /// Returns whether every map matches `f`, like [`Iterator::all`]
+    #[inline]
+    pub fn all_maps<F: Fn(&Beatmap) -> bool>(&self, f: F) -> bool {
+        self.maps.iter().all(f)
+    }
+    /// Counts the maps matching `f`.
+    ///
+    /// ```
+    /// # use blist::{beatmap::BeatmapType, Beatmap, Playlist};
+    /// let mut playlist = Playlist::new("playlist".to_owned());
+    /// playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+    /// playlist.maps.push(Beatmap::new_hash("0123456789abcdef0123456789abcdef01234567".to_owned()));
+    ///
+    /// assert_eq!(playlist.count_maps_matching(|m| m.ty == BeatmapType::Hash), 1);
+    /// ```
+    #[inline]
+    pub fn count_maps_matching<F: Fn(&Beatmap) -> bool>(&self, f: F) -> usize {
+        self.maps.iter().filter(|m| f(m)).count()
+    }
+    /// Keeps only the maps matching `f`, like [`Vec::retain`].
+    ///
+    /// The playlist isn't validated again afterwards, and an empty map list is valid.
+    #[inline]
+    pub fn retain<F: FnMut(&Beatmap) -> bool>(&mut self, f: F) {
+        self.maps.retain(f);
+    }
+    #[inline]
+    pub fn filter_maps<F: Fn(&Beatmap) -> bool>(mut self, predicate: F) -> Self {
+        self.retain(predicate);
+        self
+    }
+
+    /// Splits the maps into playlists of at most `chunk_size` maps, numbering their titles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn split_into_chunks(&self, chunk_size: usize) -> Vec<Playlist> {
+        let chunks = self.maps.chunks(chunk_size);
+        let count = chunks.len();
+        chunks
+            .enumerate()
+            .map(|(i, maps)| {
+                let mut playlist = self.with_maps(maps.to_vec());
+                playlist.title = format!("{} ({}/{})", self.title, i + 1, count);
+                playlist
+            })
+            .collect()
+    }
+    /// Splits the maps into a playlist of those matching `predicate` and one of the others
+    pub fn split_by<F: Fn(&Beatmap) -> bool>(&self, predicate: F) -> (Playlist, Playlist) {
+        let (matching, others) = self.maps.iter().cloned().partition(|m| predicate(m));
+        (self.with_maps(matching), self.with_maps(others))
+    }
+    /// Groups the maps by their most common characteristic, with ties going to the first one
+    /// listed and maps without difficulties going to `Standard`. Each playlist has the
+    /// characteristic appended to its title.
+    ///
+    /// Characteristics are compared case-insensitively, and known ones use their canonical
+    /// spelling.
+    pub fn split_by_characteristic(&self) -> HashMap<String, Playlist> {
+        let same = |a: &BeatmapCharacteristic, b: &BeatmapCharacteristic| {
+            a.as_str().eq_ignore_ascii_case(b.as_str())
+        };
+
+        let mut groups: Vec<(BeatmapCharacteristic, Vec<Beatmap>)> = Vec::new();
+        for m in &self.maps {
+            let mut counts: Vec<(BeatmapCharacteristic, usize)> = Vec::new();
+            for d in &m.difficulties {
+                let characteristic = d.characteristic_typed();
+                match counts.iter_mut().find(|(c, _)| same(c, &characteristic)) {
+                    Some((_, n)) => *n += 1,
+                    None => counts.push((characteristic, 1)),
+                }
+            }
+            let mut primary = (BeatmapCharacteristic::Standard, 0);
+            for c in counts {
+                if c.1 > primary.1 {
+                    primary = c;
+                }
+            }
+            match groups.iter_mut().find(|(c, _)| same(c, &primary.0)) {
+                Some((_, maps)) => maps.push(m.clone()),
+                None => groups.push((primary.0, vec![m.clone()])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(characteristic, maps)| {
+                let mut playlist = self.with_maps(maps);
+                playlist.title = format!("{} ({})", self.title, characteristic);
+                (characteristic.as_str().to_owned(), playlist)
+            })
+            .collect()
+    }
+    fn with_maps(&self, maps: Vec<Beatmap>) -> Playlist {
+        Playlist {
+            _schema: self._schema,
+            original_schema: self.original_schema.clone(),
+            title: self.title.clone(),
+            author: self.author.clone(),
+            description: self.description.clone(),
+            tags: self.tags.clone(),
+            cover: self.cover.clone(),
+            cover_set: self.cover_set.clone(),
+            map_order: self.map_order,
+            maps,
+            custom_data: self.custom_data.clone(),
+            validator: self.validator.clone(),
+        }
+    }
+
+    pub fn add_map(&mut self, map: Beatmap) -> Result<(), Error> {
+        if let Err(error) = self.validator.validate_map(&map) {
+            return Err(PlaylistError::InvalidBeatmap {
+                idx: self.maps.len(),
+                error,
+            }
+            .into());
+        }
+        self.maps.push(map);
+        Ok(())
+    }
+
+    /// Passes the playlist through `f`, allowing transformations to be chained.
+    ///
+    /// ```
+    /// # use blist::Playlist;
+    /// fn with_author(mut playlist: Playlist) -> Playlist {
+    ///     playlist.author = Some("author".to_owned());
+    ///     playlist
+    /// }
+    /// fn without_dates(mut playlist: Playlist) -> Playlist {
+    ///     playlist.clear_all_dates();
+    ///     playlist
+    /// }
+    ///
+    /// let playlist = Playlist::new("playlist".to_owned())
+    ///     .apply(with_author)
+    ///     .apply(without_dates);
+    /// assert_eq!(playlist.author.as_deref(), Some("author"));
+    /// ```
+    #[inline]
+    pub fn apply<F: FnOnce(Playlist) -> Playlist>(self, f: F) -> Playlist {
+        f(self)
+    }
+    /// Fallible counterpart to [`apply`](Self::apply), meant to be chained with `?`.
+    ///
+    /// ```
+    /// # use blist::{Error, Playlist};
+    /// fn validated(playlist: Playlist) -> Result<Playlist, Error> {
+    ///     playlist.validate()?;
+    ///     Ok(playlist)
+    /// }
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// let playlist = Playlist::new("playlist".to_owned()).try_apply(validated)?;
+    /// assert!(Playlist::new("".to_owned()).try_apply(validated).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn try_apply<F, E>(self, f: F) -> Result<Playlist, E>
+    where
+        F: FnOnce(Playlist) -> Result<Playlist, E>,
+    {
+        f(self)
+    }
+
+    #[inline]
+    pub fn into_maps(self) -> Vec<Beatmap> {
+        self.maps
+    }
+    pub fn into_metadata(self) -> PlaylistMetadata {
+        PlaylistMetadata {
+            title: self.title,
+            author: self.author,
+            description: self.description,
+            tags: self.tags,
+            cover: self.cover,
+            cover_set: self.cover_set,
+            map_order: self.map_order,
+            custom_data: self.custom_data,
+        }
+    }
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(
+        self,
+    ) -> (
+        String,
+        Option<String>,
+        Option<String>,
+        Option<PlaylistCover>,
+        Vec<Beatmap>,
+        IndexMap<String, Value>,
+    ) {
+        (
+            self.title,
+            self.author,
+            self.description,
+            self.cover,
+            self.maps,
+            self.custom_data,
+        )
+    }
+
+    /// Returns an approximation of the memory used by the playlist, in bytes.
+    ///
+    /// Beatmaps are only accounted for by their inline size and custom data isn't accounted for
+    /// at all, so this should only be used for rough memory accounting.
+    pub fn len_bytes(&self) -> usize {
+        self.title.len()
+            + self.author.as_ref().map_or(0, String::len)
+            + self.description.as_ref().map_or(0, String::len)
+            + self.maps.len() * mem::size_of::<Beatmap>()
+            + self.cover.as_ref().map_or(0, |c| c.data.len())
+    }
+
+    /// Removes the given machine-specific keys from the playlist's and beatmaps' custom data, and
+    /// normalizes the cover paths to use forward slashes.
+    pub fn make_portable(&mut self, machine_specific_keys: &[&str]) {
+        for &k in machine_specific_keys {
+            self.custom_data.shift_remove(k);
+            for m in &mut self.maps {
+                m.custom_data.shift_remove(k);
+            }
+        }
+        let cover_set_entries = self.cover_set.iter_mut().flat_map(CoverSet::entries_mut);
+        for c in self
+            .cover
+            .iter_mut()
+            .chain(cover_set_entries.map(|(_, c)| c))
+        {
+            c.path = PathBuf::from(c.path.to_string_lossy().replace('\\', "/"));
+        }
+    }
+
+    /// Validates the playlist, also logging a warning if the `$schema` URL isn't [`SCHEMA`] when
+    /// the `log` feature is enabled
+    pub fn validate(&self) -> Result<(), Error> {
+        #[cfg(feature = "log")]
+        if self.schema_is_stale() {
+            log::warn!(
+                "playlist `$schema` is {:?} instead of {:?}",
+                self.original_schema,
+                SCHEMA
+            );
+        }
+        Ok(self.validate_inner(true)?)
+    }
+    /// Returns whether the `$schema` URL was changed, usually by editing the JSON manually
+    #[inline]
+    pub fn schema_is_stale(&self) -> bool {
+        self.original_schema.as_deref().is_some_and(|s| s != SCHEMA)
+    }
+    /// Like [`Playlist::validate`], but keeps going after the first error and returns all of them
+    pub fn validate_all(&self) -> Result<(), Vec<Error>> {
+        let errors = self.validator.validate_all_inner(self, true);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.into_iter().map(Error::from).collect())
+        }
+    }
+    /// Validates the title, author, description and covers, skipping the maps
+    pub fn validate_metadata_only(&self) -> Result<(), Error> {
+        match self.validator.metadata_errors(self, true).next() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+    /// Validates the maps, skipping the title, author, description and covers
+    pub fn validate_maps_only(&self) -> Result<(), Error> {
+        match self.validator.maps_errors(self).next() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+    /// Validates only the map at `index`, failing with [`PlaylistError::MapIndexOutOfBounds`] if
+    /// there is no such map.
+    pub fn validate_map_at(&self, index: usize) -> Result<(), Error> {
+        let map = self
+            .maps
+            .get(index)
+            .ok_or(PlaylistError::MapIndexOutOfBounds {
+                idx: index,
+                len: self.maps.len(),
+            })?;
+        if let Err(error) = self.validator.validate_map(map) {
+            return Err(PlaylistError::InvalidBeatmap { idx: index, error }.into());
+        }
+        Ok(())
+    }
+
+    /// Fixes the problems that can be fixed without losing information, returning a description
+    /// of every change.
+    pub fn repair(&mut self) -> Vec<String> {
+        fn single_line(s: &str) -> String {
+            s.split(&['\n', '\r'][..])
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+
+        let mut changes = Vec::new();
+        if self.schema_is_stale() {
+            self.original_schema = None;
+            changes.push("reset the schema URL".to_owned());
+        }
+        if utils::str_is_empty_or_has_newlines(&self.title) && !self.title.is_empty() {
+            self.title = single_line(&self.title);
+            changes.push("joined the lines of the title".to_owned());
+        }
+        if let Some(a) = &self.author {
+            if utils::str_is_empty_or_has_newlines(a) {
+                let a = single_line(a);
+                if a.is_empty() {
+                    self.author = None;
+                    changes.push("removed the empty author".to_owned());
+                } else {
+                    self.author = Some(a);
+                    changes.push("joined the lines of the author".to_owned());
+                }
+            }
+        }
+        if let Some(d) = &self.description {
+            if d.is_empty() {
+                self.description = None;
+                changes.push("removed the empty description".to_owned());
+            } else if !self.validator.allow_newlines_in_description
+                && utils::str_is_empty_or_has_newlines(d)
+            {
+                self.description = Some(single_line(d));
+                changes.push("joined the lines of the description".to_owned());
+            }
+        }
+
+        for (idx, m) in self.maps.iter_mut().enumerate() {
+            for field in m.drop_extra_identifiers() {
+                changes.push(format!("removed the extra {} of map {}", field, idx));
+            }
+        }
+
+        changes
+    }
+    /// Runs [`Playlist::repair`], then removes the maps that are still invalid.
+    ///
+    /// Returns whether the playlist is valid afterwards along with a description of every change.
+    pub fn validate_and_repair(&mut self) -> (bool, Vec<String>) {
+        let mut changes = self.repair();
+
+        if self.validate().is_err() {
+            let invalid: BTreeSet<usize> = self
+                .validator
+                .validate_all_inner(self, true)
+                .into_iter()
+                .filter_map(|e| match e {
+                    PlaylistError::InvalidBeatmap { idx, .. } => Some(idx),
+                    _ => None,
+                })
+                .collect();
+            let mut idx = 0;
+            self.maps.retain(|_| {
+                idx += 1;
+                !invalid.contains(&(idx - 1))
+            });
+            changes.extend(invalid.iter().map(|i| format!("removed invalid map {}", i)));
+        }
+
+        (self.validate().is_ok(), changes)
+    }
+
+    #[inline]
+    // Lets playlists written before the identifiers were validated against the type still be read
+    pub(crate) fn drop_extra_identifiers(&mut self) {
+        for m in &mut self.maps {
+            m.drop_extra_identifiers();
+        }
+    }
+    pub(crate) fn validate_inner(&self, validate_cover: bool) -> Result<(), PlaylistError> {
+        self.validator.validate_inner(self, validate_cover)
+    }
+}
+
+impl Extend<Beatmap> for Playlist {
+    #[inline]
+    fn extend<T: IntoIterator<Item = Beatmap>>(&mut self, iter: T) {
+        self.maps.extend(iter)
+    }
+}
+
+impl TryFrom<&[u8]> for Playlist {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<Playlist> for Vec<u8> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(playlist: Playlist) -> Result<Self, Self::Error> {
+        playlist.to_bytes()
+    }
+}
+
+impl IntoIterator for Playlist {
+    type Item = Beatmap;
+    type IntoIter = vec::IntoIter<Beatmap>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.maps.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Playlist {
+    type Item = &'a Beatmap;
+    type IntoIter = slice::Iter<'a, Beatmap>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.maps.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Playlist {
+    type Item = &'a mut Beatmap;
+    type IntoIter = slice::IterMut<'a, Beatmap>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.maps.iter_mut()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ReadOptions {
+    pub infer_missing_type: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PlaylistWriteOptions {
+    pub compression: CompressionMethod,
+    /// Compression level passed to the underlying encoder, `None` using its default
+    pub compression_level: Option<i32>,
+}
+
+impl Default for PlaylistWriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: CompressionMethod::Deflated,
+            compression_level: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MapOrder {
+    Insertion,
+    DateAscending,
+    DateDescending,
+    Custom,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PlaylistStats {
+    pub key_count: usize,
+    pub hash_count: usize,
+    pub level_id_count: usize,
+    pub total: usize,
+    pub has_cover: bool,
+}
+
+impl fmt::Display for PlaylistStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "maps: {}", self.total)?;
+        writeln!(f, "  keys: {}", self.key_count)?;
+        writeln!(f, "  hashes: {}", self.hash_count)?;
+        writeln!(f, "  level IDs: {}", self.level_id_count)?;
+        write!(f, "cover: {}", if self.has_cover { "yes" } else { "no" })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlaylistDiff<'a> {
+    pub added: Vec<&'a Beatmap>,
+    pub removed: Vec<&'a Beatmap>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlaylistMetadata {
+    pub title: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub cover: Option<PlaylistCover>,
+    pub cover_set: Option<CoverSet>,
+    pub map_order: Option<MapOrder>,
+    pub custom_data: IndexMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct PlaylistCover {
+    #[serde(rename = "cover")]
+    pub path: PathBuf,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+    #[serde(skip)]
+    pub ty: PlaylistCoverType,
+}
+
+impl PlaylistCover {
+    pub fn from_png_bytes(data: Vec<u8>) -> Result<Self, PlaylistCoverError> {
+        let cover = Self {
+            path: PathBuf::from("cover.png"),
+            data,
+            ty: PlaylistCoverType::Png,
+        };
+        cover.validate()?;
+        Ok(cover)
+    }
+    pub fn from_jpg_bytes(data: Vec<u8>) -> Result<Self, PlaylistCoverError> {
+        let cover = Self {
+            path: PathBuf::from("cover.jpg"),
+            data,
+            ty: PlaylistCoverType::Jpg,
+        };
+        cover.validate()?;
+        Ok(cover)
+    }
+    pub fn from_webp_bytes(data: Vec<u8>) -> Result<Self, PlaylistCoverError> {
+        let cover = Self {
+            path: PathBuf::from("cover.webp"),
+            data,
+            ty: PlaylistCoverType::Webp,
+        };
+        cover.validate()?;
+        Ok(cover)
+    }
+
+    #[cfg(feature = "image")]
+    pub fn convert_to_jpg(&self, quality: u8) -> Result<PlaylistCover, Error> {
+        use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+
+        // JPEG has no alpha channel
+        let image = DynamicImage::ImageRgb8(self.decode()?.to_rgb8());
+        let mut data = Vec::new();
+        JpegEncoder::new_with_quality(&mut data, quality).encode_image(&image)?;
+        Ok(PlaylistCover {
+            path: self.path.with_extension("jpg"),
+            data,
+            ty: PlaylistCoverType::Jpg,
+        })
+    }
+    #[cfg(feature = "image")]
+    pub fn convert_to_png(&self) -> Result<PlaylistCover, Error> {
+        let mut data = Vec::new();
+        self.decode()?
+            .write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)?;
+        Ok(PlaylistCover {
+            path: self.path.with_extension("png"),
+            data,
+            ty: PlaylistCoverType::Png,
+        })
+    }
+    #[cfg(feature = "image")]
+    fn decode(&self) -> Result<image::DynamicImage, Error> {
+        let format = match self.ty {
+            PlaylistCoverType::Png => image::ImageFormat::Png,
+            PlaylistCoverType::Jpg => image::ImageFormat::Jpeg,
+            PlaylistCoverType::Webp => image::ImageFormat::WebP,
+            PlaylistCoverType::Unknown => {
+                return Err(Error::Validation(
+                    PlaylistCoverError::UnknownCoverType.into(),
+                ))
+            }
+        };
+        Ok(image::load_from_memory_with_format(&self.data, format)?)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), PlaylistCoverError> {
+        match self.ty {
+            PlaylistCoverType::Png => {
+                if utils::path_is_invalid(&self.path) || self.path.extension().unwrap() != "png" {
+                    return Err(PlaylistCoverError::InvalidCoverPath {
+                        ty: "png",
+                        path: self.path.clone(),
+                    });
+                }
+                if self.data.len() < PNG_MAGIC_NUMBER_LEN
+                    || !constant_time_eq::constant_time_eq(
+                        &self.data[..PNG_MAGIC_NUMBER_LEN],
+                        PNG_MAGIC_NUMBER,
+                    )
+                {
+                    return Err(PlaylistCoverError::InvalidCoverData { ty: "png" });
+                }
+            }
+            PlaylistCoverType::Jpg => {
+                if utils::path_is_invalid(&self.path) {
+                    return Err(PlaylistCoverError::InvalidCoverPath {
+                        ty: "jpg",
+                        path: self.path.clone(),
+                    });
+                }
+                let ext = self.path.extension().unwrap();
+                if ext != "jpg" && ext != "jpeg" {
+                    return Err(PlaylistCoverError::InvalidCoverPath {
+                        ty: "jpg",
+                        path: self.path.clone(),
+                    });
+                }
+                if self.data.len() < JPG_MAGIC_NUMBER_LEN
+                    || !constant_time_eq::constant_time_eq(
+                        &self.data[..JPG_MAGIC_NUMBER_LEN],
+                        JPG_MAGIC_NUMBER,
+                    )
+                {
+                    return Err(PlaylistCoverError::InvalidCoverData { ty: "jpg" });
+                }
+            }
+            PlaylistCoverType::Webp => {
+                if utils::path_is_invalid(&self.path) || self.path.extension().unwrap() != "webp" {
+                    return Err(PlaylistCoverError::InvalidCoverPath {
+                        ty: "webp",
+                        path: self.path.clone(),
+                    });
+                }
+                if !utils::webp_magic_number_matches(&self.data) {
+                    return Err(PlaylistCoverError::InvalidCoverData { ty: "webp" });
+                }
+            }
+            PlaylistCoverType::Unknown => return Err(PlaylistCoverError::UnknownCoverType),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct CoverSet {
+    #[serde(
+        rename = "coverSmall",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "cover_set_path"
+    )]
+    pub thumbnail: Option<PlaylistCover>,
+    #[serde(
+        rename = "coverLarge",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "cover_set_path"
+    )]
+    pub full: Option<PlaylistCover>,
+}
+
+impl CoverSet {
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&'static str, &PlaylistCover)> {
+        let thumbnail = self.thumbnail.as_ref().map(|c| ("coverSmall", c));
+        let full = self.full.as_ref().map(|c| ("coverLarge", c));
+        thumbnail.into_iter().chain(full)
+    }
+    pub(crate) fn entries_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&'static str, &mut PlaylistCover)> {
+        let thumbnail = self.thumbnail.as_mut().map(|c| ("coverSmall", c));
+        let full = self.full.as_mut().map(|c| ("coverLarge", c));
+        thumbnail.into_iter().chain(full)
+    }
+
+    fn deserialize_optional<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Self>, D::Error> {
+        let cs = Self::deserialize(deserializer)?;
+        if cs.thumbnail.is_none() && cs.full.is_none() {
+            Ok(None)
+        } else {
+            Ok(Some(cs))
+        }
+    }
+}
+
+mod cover_set_path {
+    use super::PlaylistCover;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::path::PathBuf;
+
+    pub fn serialize<S: Serializer>(
+        cover: &Option<PlaylistCover>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        cover.as_ref().map(|c| &c.path).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<PlaylistCover>, D::Error> {
+        Ok(
+            Option::<PathBuf>::deserialize(deserializer)?.map(|path| PlaylistCover {
+                path,
+                data: Vec::new(),
+                ty: Default::default(),
+            }),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PlaylistCoverType {
+    Png,
+    Jpg,
+    Webp,
+    Unknown,
+}
+
+impl Default for PlaylistCoverType {
+    #[inline]
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        beatmap::{BeatmapDifficulty, BeatmapIdentifier, BeatmapType},
+        playlist::{
+            CoverSet, MapOrder, PlaylistCover, PlaylistCoverType, PlaylistWriteOptions, ReadOptions,
+        },
+        utils::{JPG_MAGIC_NUMBER, PNG_MAGIC_NUMBER},
+        validation::{BeatmapError, PlaylistCoverError, PlaylistError, Validator},
+        Beatmap, Error, Playlist,
+    };
+    use chrono::Utc;
+    use serde_json::Value;
+    use std::{
+        io::{Cursor, Write},
+        path::{Path, PathBuf},
+    };
+    use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+    #[test]
+    fn write_and_read() {
+        let mut old = Playlist::new("playlist".to_owned());
+        old.author = Some("author".to_owned());
+        old.description = Some("description".to_owned());
+        old.custom_data
+            .insert("key".to_owned(), Value::String("value".to_owned()));
+
+        let mut map = Beatmap::new_key("16af".to_owned());
+        map.difficulties.push(BeatmapDifficulty {
+            name: "Expert+".to_owned(),
+            characteristic: "normal".to_owned(),
+        });
+        old.add_map(map).unwrap();
+        old.extend(vec![
+            Beatmap::new_hash("0123456789abcdef0123456789abcdef01234567".to_string()),
+            Beatmap::new_level_id("level ID".to_string()),
+        ]);
+
+        let mut buffer = Cursor::new(Vec::new());
+        old.write(&mut buffer).unwrap();
+
+        buffer.set_position(0);
+        let new = Playlist::read(&mut buffer).unwrap();
+
+        assert_eq!(old, new);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn convert_cover() {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgba8(4, 3)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let cover = PlaylistCover {
+            path: PathBuf::from("cover.png"),
+            data: png,
+            ty: PlaylistCoverType::Png,
+        };
+
+        let jpg = cover.convert_to_jpg(90).unwrap();
+        assert_eq!(jpg.ty, PlaylistCoverType::Jpg);
+        assert_eq!(jpg.path, PathBuf::from("cover.jpg"));
+        assert!(jpg.validate().is_ok());
+
+        let mut playlist = Playlist::new("playlist".to_owned());
+        assert_eq!(playlist.cover_pixel_dimensions(), None);
+        playlist.cover = Some(cover);
+        assert_eq!(playlist.cover_pixel_dimensions(), Some((4, 3)));
+        playlist.cover = Some(jpg.clone());
+        assert_eq!(playlist.cover_pixel_dimensions(), Some((4, 3)));
+
+        let png = jpg.convert_to_png().unwrap();
+        assert_eq!(png.ty, PlaylistCoverType::Png);
+        assert_eq!(png.path, PathBuf::from("cover.png"));
+        assert!(png.validate().is_ok());
+    }
+
+    #[test]
+    fn webp_cover() {
+        // Minimal extended WebP header for a 3x2 canvas
+        let mut webp = b"RIFF\x16\0\0\0WEBPVP8X\x0A\0\0\0".to_vec();
+        webp.extend_from_slice(&[0, 0, 0, 0, 2, 0, 0, 1, 0, 0]);
+
+        let mut old = Playlist::new("playlist".to_owned());
+        old.set_webp_cover(webp.as_slice()).unwrap();
+        assert_eq!(old.cover_pixel_dimensions(), Some((3, 2)));
+
+        let new = Playlist::from_bytes(&old.to_bytes().unwrap()).unwrap();
+        let cover = new.cover.unwrap();
+        assert_eq!(cover.ty, PlaylistCoverType::Webp);
+        assert_eq!(cover.path, PathBuf::from("cover.webp"));
+        assert_eq!(cover.data, webp);
+
+        old.cover.as_mut().unwrap().data[8] = b'X';
+        assert!(old.to_bytes().is_err());
+    }
+
+    #[test]
+    fn set_cover_raw() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .set_cover_raw(JPG_MAGIC_NUMBER.to_vec(), PlaylistCoverType::Jpg)
+            .unwrap();
+        assert_eq!(
+            playlist.cover_path(),
+            Some(PathBuf::from("cover.jpg").as_path())
+        );
+        assert!(playlist.to_bytes().is_ok());
+
+        playlist
+            .set_cover_raw(JPG_MAGIC_NUMBER.to_vec(), PlaylistCoverType::Png)
+            .unwrap();
+        assert!(playlist.to_bytes().is_err());
+        assert!(playlist
+            .set_cover_raw(Vec::new(), PlaylistCoverType::Unknown)
+            .is_err());
+    }
+
+    #[test]
+    fn cover_from_bytes() {
+        let png = PlaylistCover::from_png_bytes(PNG_MAGIC_NUMBER.to_vec()).unwrap();
+        assert_eq!(png.ty, PlaylistCoverType::Png);
+        assert!(png.validate().is_ok());
+        let jpg = PlaylistCover::from_jpg_bytes(JPG_MAGIC_NUMBER.to_vec()).unwrap();
+        assert_eq!(jpg.path, PathBuf::from("cover.jpg"));
+        assert!(jpg.validate().is_ok());
+
+        assert!(PlaylistCover::from_png_bytes(JPG_MAGIC_NUMBER.to_vec()).is_err());
+        assert!(PlaylistCover::from_jpg_bytes(PNG_MAGIC_NUMBER.to_vec()).is_err());
+        assert!(PlaylistCover::from_webp_bytes(PNG_MAGIC_NUMBER.to_vec()).is_err());
+    }
+
+    #[test]
+    fn cover_accessors() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        assert!(!playlist.has_cover());
+        assert_eq!(playlist.cover_type(), None);
+        assert_eq!(playlist.cover_data(), None);
+
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        assert!(playlist.has_cover());
+        assert_eq!(playlist.cover_type(), Some(&PlaylistCoverType::Png));
+        assert_eq!(playlist.cover_data(), Some(PNG_MAGIC_NUMBER.as_ref()));
+
+        playlist.remove_cover();
+        assert!(!playlist.has_cover());
+        assert_eq!(playlist.cover_type(), None);
+        assert_eq!(playlist.cover_data(), None);
+    }
+
+    #[test]
+    fn with_validator() {
+        let validator = Validator {
+            max_maps: Some(1),
+            ..Validator::default()
+        };
+        let mut playlist = Playlist::new("playlist".to_owned()).with_validator(validator);
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+
+        let bytes = playlist.to_bytes().unwrap();
+        let read = Playlist::from_bytes(&bytes).unwrap();
+        assert_eq!(read.validator, Validator::default());
+        assert_eq!(read, playlist);
+
+        playlist.maps.push(Beatmap::new_key("3c4d".to_owned()));
+        assert!(matches!(
+            playlist.validate(),
+            Err(Error::Validation(PlaylistError::TooManyMaps {
+                count: 2,
+                max: 1
+            }))
+        ));
+        assert!(playlist.to_bytes().is_err());
+        let playlist = playlist.with_validator(Validator::default());
+        assert!(playlist.to_bytes().is_ok());
+    }
+
+    #[test]
+    fn cover_pixel_dimensions_from_headers() {
+        let mut png = PNG_MAGIC_NUMBER.to_vec();
+        png.extend_from_slice(b"\0\0\0\x0dIHDR");
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .set_cover_raw(png.clone(), PlaylistCoverType::Png)
+            .unwrap();
+        assert_eq!(playlist.cover_pixel_dimensions(), Some((640, 480)));
+        playlist
+            .set_cover_raw(png[..20].to_vec(), PlaylistCoverType::Png)
+            .unwrap();
+        assert_eq!(playlist.cover_pixel_dimensions(), None);
+
+        // JFIF APP0, then a Huffman table that shares the SOF marker range, then a progressive SOF
+        let mut jpg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        jpg.extend_from_slice(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        jpg.extend_from_slice(&[0xFF, 0xFF, 0xC4, 0x00, 0x03, 0x00]);
+        jpg.extend_from_slice(&[0xFF, 0xC2, 0x00, 0x0B, 0x08]);
+        jpg.extend_from_slice(&300u16.to_be_bytes());
+        jpg.extend_from_slice(&400u16.to_be_bytes());
+        jpg.extend_from_slice(&[0x01, 0x01, 0x11, 0x00]);
+
+        playlist
+            .set_cover_raw(jpg.clone(), PlaylistCoverType::Jpg)
+            .unwrap();
+        assert_eq!(playlist.cover_pixel_dimensions(), Some((400, 300)));
+        playlist
+            .set_cover_raw(jpg[..jpg.len() - 8].to_vec(), PlaylistCoverType::Jpg)
+            .unwrap();
+        assert_eq!(playlist.cover_pixel_dimensions(), None);
+    }
+
+    #[test]
+    fn make_portable() {
+        let mut playlist = Playlist::new("playlist".to_owned())
+            .with_custom_data_entry("localPath", "C:\\Songs")
+            .unwrap();
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        playlist.cover_set = Some(CoverSet {
+            thumbnail: Some(PlaylistCover {
+                path: PathBuf::from("small.jpg"),
+                data: JPG_MAGIC_NUMBER.to_vec(),
+                ty: PlaylistCoverType::Jpg,
+            }),
+            full: None,
+        });
+        playlist.maps.push(
+            Beatmap::new_key("1a2b".to_owned())
+                .with_custom_data_entry("localPath", "C:\\Songs\\1a2b")
+                .unwrap(),
+        );
+
+        let mut backslashes = playlist.clone();
+        backslashes
+            .cover_set
+            .as_mut()
+            .unwrap()
+            .thumbnail
+            .as_mut()
+            .unwrap()
+            .path = PathBuf::from("covers\\small.jpg");
+        backslashes.make_portable(&["localPath"]);
+        assert!(backslashes.custom_data.is_empty());
+        assert!(backslashes.maps[0].custom_data.is_empty());
+        assert_eq!(
+            backslashes.thumbnail_cover().unwrap().path,
+            PathBuf::from("covers/small.jpg")
+        );
+
+        playlist.make_portable(&["localPath"]);
+        let first = playlist.to_bytes().unwrap();
+        let second = playlist.to_bytes().unwrap();
+        assert_eq!(first, second);
+        let fixed = zip::DateTime::default();
+        let mut zip = ZipArchive::new(Cursor::new(first)).unwrap();
+        for i in 0..zip.len() {
+            let modified = zip.by_index(i).unwrap().last_modified().unwrap();
+            assert_eq!(
+                (modified.datepart(), modified.timepart()),
+                (fixed.datepart(), fixed.timepart())
+            );
+        }
+    }
+
+    #[test]
+    fn cover_set() {
+        let mut old = Playlist::new("playlist".to_owned());
+        old.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        old.cover_set = Some(CoverSet {
+            thumbnail: Some(PlaylistCover {
+                path: PathBuf::from("small.jpg"),
+                data: JPG_MAGIC_NUMBER.to_vec(),
+                ty: PlaylistCoverType::Jpg,
+            }),
+            full: Some(PlaylistCover {
+                path: PathBuf::from("large.png"),
+                data: PNG_MAGIC_NUMBER.to_vec(),
+                ty: PlaylistCoverType::Png,
+            }),
+        });
+
+        let mut buffer = Cursor::new(Vec::new());
+        old.write(&mut buffer).unwrap();
+
+        buffer.set_position(0);
+        let new = Playlist::read(&mut buffer).unwrap();
+
+        assert_eq!(old, new);
+        assert_eq!(new.thumbnail_cover().unwrap().ty, PlaylistCoverType::Jpg);
+
+        let mut duplicate_path = new;
+        duplicate_path.cover_set.as_mut().unwrap().full = duplicate_path.cover.clone();
+        assert!(duplicate_path.validate().is_err());
+    }
+
+    #[test]
+    fn builder() {
+        let built = Playlist::new("playlist".to_owned())
+            .with_author("author")
+            .with_description("description")
+            .unwrap()
+            .with_custom_data_entry("key", "value")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut manual = Playlist::new("playlist".to_owned());
+        manual.author = Some("author".to_owned());
+        manual.description = Some("description".to_owned());
+        manual
+            .custom_data
+            .insert("key".to_owned(), Value::String("value".to_owned()));
+
+        assert_eq!(built, manual);
+        assert!(Playlist::new("playlist".to_owned())
+            .with_description("")
+            .is_err());
+        assert!(Playlist::new("".to_owned()).build().is_err());
+
+        let date = Utc::now();
+        let difficulty = BeatmapDifficulty {
+            name: "Expert+".to_owned(),
+            characteristic: "Standard".to_owned(),
+        };
+        let built = Beatmap::new_key("16af".to_owned())
+            .with_date(date)
+            .with_difficulty(difficulty.clone())
+            .with_custom_data_entry("key", true)
+            .unwrap()
+            .with_custom_data_entry("bpm", 120.0)
+            .unwrap();
+
+        let mut manual = Beatmap::new_key("16af".to_owned());
+        manual.date = Some(date);
+        manual.difficulties.push(difficulty);
+        manual
+            .custom_data
+            .insert("key".to_owned(), Value::Bool(true));
+        manual.custom_data.insert("bpm".to_owned(), 120.0.into());
+
+        assert_eq!(built, manual);
+    }
+
+    #[test]
+    fn add_map() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .add_map(Beatmap::new_key("16af".to_owned()))
+            .unwrap();
+
+        let before = playlist.clone();
+        assert!(playlist
+            .add_map(Beatmap::new_key("invalid".to_owned()))
+            .is_err());
+        assert_eq!(playlist, before);
+    }
+
+    #[test]
+    fn read_cover_only() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+
+        let mut buffer = Cursor::new(Vec::new());
+        playlist.write(&mut buffer).unwrap();
+        buffer.set_position(0);
+        assert_eq!(Playlist::read_cover_only(&mut buffer).unwrap(), None);
+
+        playlist
+            .set_cover_raw(PNG_MAGIC_NUMBER.to_vec(), PlaylistCoverType::Png)
+            .unwrap();
+        let mut buffer = Cursor::new(Vec::new());
+        playlist.write(&mut buffer).unwrap();
+        buffer.set_position(0);
+        assert_eq!(
+            Playlist::read_cover_only(&mut buffer).unwrap(),
+            playlist.cover
+        );
+    }
+
+    #[test]
+    fn append_to() {
+        let playlist = Playlist::new("playlist".to_owned());
+
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("readme.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"readme").unwrap();
+        zip.finish().unwrap();
+
+        playlist.append_to(&mut buffer).unwrap();
+        buffer.set_position(0);
+        assert_eq!(Playlist::read(&mut buffer).unwrap(), playlist);
+        buffer.set_position(0);
+        let entries = Playlist::zip_entries(&mut buffer).unwrap();
+        assert_eq!(entries, ["readme.txt", "playlist.json"]);
+
+        match playlist.append_to(&mut buffer) {
+            Err(Error::DuplicateZipEntry { name }) => assert_eq!(name, "playlist.json"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn read_mmap() {
+        let path = std::env::temp_dir().join(format!("blist-mmap-{}.blist", std::process::id()));
+
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+        // SAFETY: the file is private to this test and only modified between reads
+        let read = |path: &std::path::Path| unsafe { Playlist::read_mmap(path) };
+
+        playlist.write_to_path(&path).unwrap();
+        assert_eq!(read(&path).unwrap(), playlist);
+
+        std::fs::write(&path, b"not a zip archive").unwrap();
+        assert!(matches!(read(&path), Err(Error::Zip(_))));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(read(&path), Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn update_in_place() {
+        let path = std::env::temp_dir().join(format!("blist-update-{}.blist", std::process::id()));
+
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .unwrap();
+            let mut zip = ZipWriter::new(file);
+            zip.start_file("readme.txt", SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"readme").unwrap();
+            let mut file = zip.finish().unwrap();
+            playlist.append_to(&mut file).unwrap();
+        }
+
+        playlist.title = "updated".to_owned();
+        playlist.remove_cover();
+        playlist.update_in_place(&path).unwrap();
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+        let entries = Playlist::zip_entries(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(entries, ["readme.txt", "playlist.json"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_and_write_path() {
+        let path = std::env::temp_dir().join(format!("blist-{}.blist", std::process::id()));
+
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        playlist.write_to_path(&path).unwrap();
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+
+        playlist.title = "atomic".to_owned();
+        playlist.write_to_path_atomic(&path).unwrap();
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+
+        let invalid = Playlist::new("".to_owned());
+        assert!(invalid.write_to_path(&path).is_err());
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+        assert!(invalid.write_to_path_atomic(&path).is_err());
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+        assert!(invalid.update_in_place(&path).is_err());
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+
+        playlist.maps.clear();
+        playlist.update_in_place(&path).unwrap();
+        assert_eq!(Playlist::read_from_path(&path).unwrap(), playlist);
+
+        let mut changed = playlist.clone();
+        changed.maps.push(Beatmap::new_key("16af".to_owned()));
+        let (added, removed) = changed.compute_diff_from_path(&path).unwrap();
+        assert!(added.is_empty());
+        assert_eq!(removed, changed.maps);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_options() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.extend((0..256).map(|k| Beatmap::new_key(format!("{:x}", k + 1))));
+
+        let write = |compression| {
+            let options = PlaylistWriteOptions {
+                compression,
+                ..Default::default()
+            };
+            let mut buffer = Cursor::new(Vec::new());
+            playlist.write_with_options(&mut buffer, options).unwrap();
+
+            buffer.set_position(0);
+            assert_eq!(Playlist::read(&mut buffer).unwrap(), playlist);
+            buffer.into_inner().len()
+        };
+        assert!(write(zip::CompressionMethod::Stored) > write(zip::CompressionMethod::Deflated));
+    }
+
+    #[test]
+    fn find() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1a".to_owned()));
+        playlist.maps.push(Beatmap::new_key("2b".to_owned()));
+        playlist.maps.push(Beatmap::new_hash(hash.to_uppercase()));
+        playlist.maps.push(Beatmap::new_level_id("2b".to_owned()));
+
+        assert_eq!(playlist.find_by_key("2B"), Some(&playlist.maps[1]));
+        assert_eq!(playlist.find_by_key("3c"), None);
+        assert_eq!(playlist.find_by_hash(hash), Some(&playlist.maps[2]));
+        assert_eq!(
+            playlist.find_by_hash(&hash.to_uppercase()),
+            Some(&playlist.maps[2])
+        );
+        assert_eq!(playlist.find_by_hash("2b"), None);
+        assert_eq!(playlist.find_by_level_id("2b"), Some(&playlist.maps[3]));
+        assert_eq!(playlist.find_by_level_id("2B"), None);
+        assert_eq!(
+            playlist.find(&BeatmapIdentifier::Key("1a")),
+            Some(&playlist.maps[0])
+        );
+
+        playlist.find_by_key_mut("1a").unwrap().date = None;
+        assert_eq!(playlist.maps[0].date, None);
+    }
+
+    #[test]
+    fn retain() {
+        let date = |s: &str| s.parse::<chrono::DateTime<Utc>>().unwrap();
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .maps
+            .push(Beatmap::new_key("1".to_owned()).with_date(date("2020-01-01T00:00:00Z")));
+        playlist
+            .maps
+            .push(Beatmap::new_level_id("2".to_owned()).with_date(date("2020-06-01T00:00:00Z")));
+        playlist
+            .maps
+            .push(Beatmap::new_key("3".to_owned()).with_date(date("2021-01-01T00:00:00Z")));
+
+        let keys = playlist.clone().filter_maps(|m| m.ty == BeatmapType::Key);
+        assert_eq!(
+            keys.maps,
+            [playlist.maps[0].clone(), playlist.maps[2].clone()]
+        );
+        assert_eq!(keys.title, playlist.title);
+
+        let range = date("2020-03-01T00:00:00Z")..date("2021-03-01T00:00:00Z");
+        let mut in_range = playlist.clone();
+        in_range.retain(|m| m.date.is_some_and(|d| range.contains(&d)));
+        assert_eq!(in_range.maps, playlist.maps[1..]);
+
+        playlist.retain(|_| false);
+        assert!(playlist.maps.is_empty());
+        assert!(playlist.validate().is_ok());
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1".to_owned()));
+        playlist.maps.push(Beatmap::new_key("2".to_owned()));
+        playlist.maps.push(Beatmap::new_key("3".to_owned()));
+
+        assert_eq!((&playlist).into_iter().count(), 3);
+        for m in &mut playlist {
+            m.date = None;
+        }
+        assert!(playlist.iter().all(|m| m.date.is_none()));
+
+        let maps = playlist.maps.clone();
+        assert_eq!(playlist.into_iter().collect::<Vec<_>>(), maps);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn read_and_write_async() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+
+        let mut buffer = Cursor::new(Vec::new());
+        playlist.write_async(&mut buffer).await.unwrap();
+        buffer.set_position(0);
+        assert_eq!(Playlist::read_async(&mut buffer).await.unwrap(), playlist);
+
+        let path = std::env::temp_dir().join(format!("blist-async-{}.blist", std::process::id()));
+        playlist.write_to_path_async(&path).await.unwrap();
+        assert_eq!(
+            Playlist::read_from_path_async(&path).await.unwrap(),
+            playlist
+        );
+        let invalid = Playlist::new("".to_owned());
+        assert!(invalid.write_to_path_async(&path).await.is_err());
+        assert_eq!(
+            Playlist::read_from_path_async(&path).await.unwrap(),
+            playlist
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn json_value() {
+        let mut old = Playlist::new("playlist".to_owned())
+            .with_description("description".to_owned())
+            .unwrap()
+            .with_custom_data_entry("custom", true)
+            .unwrap();
+        old.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        old.maps.push(Beatmap::new_key("16af".to_owned()));
+
+        let value = old.to_json_value().unwrap();
+        assert_eq!(value["title"], "playlist");
+        assert_eq!(value["cover"], "cover.png");
+        let new = Playlist::from_json_value(value).unwrap();
+        assert_eq!(new.title, old.title);
+        assert_eq!(new.description, old.description);
+        assert_eq!(new.custom_data, old.custom_data);
+        assert_eq!(new.maps, old.maps);
+        assert!(new.cover.unwrap().data.is_empty());
+
+        let mut merged = old.clone();
+        let value = serde_json::json!({ "custom": false, "other": 1 });
+        merged.merge_custom_data_from_value(value.as_object().unwrap());
+        assert_eq!(merged.custom_data["custom"], true);
+        assert_eq!(merged.custom_data["other"], 1);
+        assert_eq!(merged.custom_data.len(), 2);
+    }
+
+    #[test]
+    fn base64() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+
+        let encoded = playlist.to_base64().unwrap();
+        assert_eq!(Playlist::read_from_base64(&encoded).unwrap(), playlist);
+
+        assert!(matches!(
+            Playlist::read_from_base64("not base64!"),
+            Err(Error::Base64(_))
+        ));
+        assert!(matches!(
+            Playlist::read_from_base64(&base64::encode(b"not a zip archive")),
+            Err(Error::Zip(_))
+        ));
+    }
+
+    #[test]
+    fn read_and_write_json() {
+        let mut old = Playlist::new("playlist".to_owned())
+            .with_author("author".to_owned())
+            .with_custom_data_entry("custom", true)
+            .unwrap();
+        old.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        old.maps.push(Beatmap::new_key("16af".to_owned()));
+
+        let mut json = Vec::new();
+        old.write_json(&mut json).unwrap();
+        let new = Playlist::read_json(json.as_slice()).unwrap();
+
+        assert_eq!(new.title, old.title);
+        assert_eq!(new.author, old.author);
+        assert_eq!(new.custom_data, old.custom_data);
+        assert_eq!(new.maps, old.maps);
+        let cover = new.cover.unwrap();
+        assert_eq!(cover.path, PathBuf::from("cover.png"));
+        assert!(cover.data.is_empty());
+        assert_eq!(cover.ty, PlaylistCoverType::Unknown);
+    }
+
+    #[test]
+    fn custom_data_order() {
+        let mut old = Playlist::new("playlist".to_owned())
+            .with_custom_data_entry("b", Value::Null)
+            .and_then(|p| p.with_custom_data_entry("a", Value::Null))
+            .unwrap();
+        old.maps.push(
+            Beatmap::new_key("16af".to_owned())
+                .with_custom_data_entry("d", Value::Null)
+                .and_then(|m| m.with_custom_data_entry("c", Value::Null))
+                .unwrap(),
+        );
+
+        let new = Playlist::from_bytes(&old.to_bytes().unwrap()).unwrap();
+        assert_eq!(new.custom_data.keys().collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!(
+            new.maps[0].custom_data.keys().collect::<Vec<_>>(),
+            ["d", "c"]
+        );
+    }
+
+    #[test]
+    fn sort() {
+        let date = |s: &str| s.parse::<chrono::DateTime<Utc>>().unwrap();
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .maps
+            .push(Beatmap::new_key("b".to_owned()).with_date(date("2020-06-01T00:00:00Z")));
+        playlist.maps.push(Beatmap {
+            date: None,
+            ..Beatmap::new_key("c".to_owned())
+        });
+        playlist
+            .maps
+            .push(Beatmap::new_key("a".to_owned()).with_date(date("2021-01-01T00:00:00Z")));
+        playlist
+            .maps
+            .push(Beatmap::new_key("d".to_owned()).with_date(date("2019-01-01T00:00:00Z")));
+
+        let keys = |p: &Playlist| p.map_filter_maps(|m| m.identifier_str().map(str::to_owned));
+        let sorted_keys = |p: &Playlist| {
+            p.maps_sorted()
+                .into_iter()
+                .filter_map(|m| m.identifier_str().map(str::to_owned))
+                .collect::<Vec<_>>()
+        };
+
+        playlist.map_order = Some(MapOrder::DateAscending);
+        assert_eq!(sorted_keys(&playlist), ["d", "b", "a", "c"]);
+        playlist.map_order = Some(MapOrder::DateDescending);
+        assert_eq!(sorted_keys(&playlist), ["a", "b", "d", "c"]);
+        playlist.map_order = Some(MapOrder::Insertion);
+        assert_eq!(sorted_keys(&playlist), ["b", "c", "a", "d"]);
+
+        playlist.sort_maps_by_date();
+        assert_eq!(keys(&playlist), ["d", "b", "a", "c"]);
+        playlist.sort_maps_by_date_desc();
+        assert_eq!(keys(&playlist), ["a", "b", "d", "c"]);
+        playlist.sort_maps(|a, b| a.identifier_str().cmp(&b.identifier_str()));
+        assert_eq!(keys(&playlist), ["a", "b", "c", "d"]);
+
+        let mut playlist = playlist.with_sorted_maps();
+        assert_eq!(keys(&playlist), ["d", "b", "a", "c"]);
+        playlist
+            .insert_map_sorted(Beatmap {
+                date: None,
+                ..Beatmap::new_key("e".to_owned())
+            })
+            .unwrap();
+        playlist
+            .insert_map_sorted(
+                Beatmap::new_key("f".to_owned()).with_date(date("2020-01-01T00:00:00Z")),
+            )
+            .unwrap();
+        playlist
+            .insert_map_sorted(
+                Beatmap::new_key("1a".to_owned()).with_date(date("2021-01-01T00:00:00Z")),
+            )
+            .unwrap();
+        assert_eq!(keys(&playlist), ["d", "f", "b", "a", "1a", "c", "e"]);
+    }
+
+    #[test]
+    fn fill_missing_dates() {
+        let date = |s: &str| s.parse::<chrono::DateTime<Utc>>().unwrap();
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .maps
+            .push(Beatmap::new_key("a".to_owned()).with_date(date("2020-06-01T00:00:00Z")));
+        playlist.maps.push(Beatmap {
+            date: None,
+            ..Beatmap::new_key("b".to_owned())
+        });
+
+        playlist.fill_missing_dates(date("2021-01-01T00:00:00Z"));
+        assert_eq!(playlist.maps[0].date, Some(date("2020-06-01T00:00:00Z")));
+        assert_eq!(playlist.maps[1].date, Some(date("2021-01-01T00:00:00Z")));
+
+        playlist.fill_missing_dates(date("2022-01-01T00:00:00Z"));
+        assert_eq!(playlist.maps[1].date, Some(date("2021-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn sort_maps_by_key_numeric() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1a0".to_owned()));
+        playlist
+            .maps
+            .push(Beatmap::new_level_id("level ID".to_owned()));
+        playlist.maps.push(Beatmap::new_key("not hex".to_owned()));
+        playlist.maps.push(Beatmap::new_key("ff".to_owned()));
+        playlist.maps.push(Beatmap::new_hash(
+            "0123456789abcdef0123456789abcdef01234567".to_owned(),
+        ));
+        playlist.maps.push(Beatmap::new_key("0A".to_owned()));
+
+        // Keys are compared as numbers rather than strings, then invalid keys and other maps
+        // follow in their original order
+        playlist.sort_maps_by_key_numeric();
+        assert_eq!(
+            playlist.map_filter_maps(|m| m.identifier_str().map(str::to_owned)),
+            [
+                "0A",
+                "ff",
+                "1a0",
+                "not hex",
+                "level ID",
+                "0123456789abcdef0123456789abcdef01234567",
+            ]
+        );
+    }
+
+    #[test]
+    fn reorder_maps() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.extend(
+            ["a", "b", "c"]
+                .iter()
+                .map(|k| Beatmap::new_key(k.to_string())),
+        );
+        let keys = |p: &Playlist| p.map_filter_maps(|m| m.identifier_str().map(str::to_owned));
+
+        playlist.reorder_maps(&[2, 0, 1]).unwrap();
+        assert_eq!(keys(&playlist), ["c", "a", "b"]);
+
+        let before = playlist.clone();
+        for permutation in [&[0, 0, 1][..], &[0, 1], &[0, 1, 2, 0], &[0, 1, 3]] {
+            match playlist.reorder_maps(permutation) {
+                Err(Error::Validation(PlaylistError::InvalidPermutation { len: 3, .. })) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            assert_eq!(playlist, before);
+        }
+    }
+
+    #[test]
+    fn split() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        assert!(playlist.split_into_chunks(2).is_empty());
+
+        playlist.extend((1..=5).map(|k| Beatmap::new_key(k.to_string())));
+        playlist
+            .maps
+            .push(Beatmap::new_level_id("level ID".to_owned()));
+
+        let chunks = playlist.split_into_chunks(4);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].title, "playlist (1/2)");
+        assert_eq!(chunks[1].title, "playlist (2/2)");
+        assert_eq!(chunks[0].maps, playlist.maps[..4]);
+        assert_eq!(chunks[1].maps, playlist.maps[4..]);
+        assert!(chunks.iter().all(|c| c.cover == playlist.cover));
+
+        let (keys, others) = playlist.split_by(|m| m.ty == BeatmapType::Key);
+        assert_eq!(keys.maps, playlist.maps[..5]);
+        assert_eq!(others.maps, playlist.maps[5..]);
+        assert_eq!(keys.title, playlist.title);
+        assert_eq!(playlist.maps.len(), 6);
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        use std::convert::{TryFrom, TryInto};
+
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+
+        let bytes: Vec<u8> = playlist.clone().try_into().unwrap();
+        assert_eq!(Playlist::try_from(bytes.as_slice()).unwrap(), playlist);
+        assert!(Playlist::try_from(&b"not a zip"[..]).is_err());
+    }
+
+    #[test]
+    fn normalize_hashes() {
+        let hash = "0123456789ABCDEF0123456789abcdef01234567";
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_hash(hash.to_owned()));
+        playlist.maps.push(Beatmap::new_key("ABC".to_owned()));
+        playlist.maps.push(Beatmap::new_hash(hash.to_lowercase()));
+        assert_eq!(
+            playlist.maps[0].hash_normalized(),
+            Some(hash.to_lowercase())
+        );
+
+        playlist.normalize_all_hashes();
+        assert_eq!(
+            playlist.maps[0].hash.as_deref(),
+            Some(&*hash.to_lowercase())
+        );
+        assert_eq!(playlist.maps[1].key.as_deref(), Some("ABC"));
+        assert!(playlist.validate().is_ok());
+
+        assert_eq!(playlist.maps[0].hash, playlist.maps[2].hash);
+        playlist.deduplicate_maps();
+        assert_eq!(playlist.maps.len(), 2);
+    }
+
+    #[test]
+    fn stats() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let mut playlist = Playlist::new("playlist".to_owned());
+        assert!(playlist.is_empty());
+        let empty = playlist.stats();
+        assert_eq!(
+            (
+                empty.key_count,
+                empty.hash_count,
+                empty.level_id_count,
+                empty.total
+            ),
+            (0, 0, 0, 0)
+        );
+        assert!(!empty.has_cover);
+
+        playlist.maps.push(Beatmap::new_hash(hash.to_owned()));
+        playlist.maps.push(Beatmap::new_hash(hash.to_uppercase()));
+        let hashes = playlist.stats();
+        assert_eq!((hashes.hash_count, hashes.total), (2, 2));
+
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        playlist
+            .maps
+            .push(Beatmap::new_level_id("level ID".to_owned()));
+        playlist.set_png_cover(PNG_MAGIC_NUMBER.as_ref()).unwrap();
+        let mixed = playlist.stats();
+        assert_eq!(playlist.len(), 4);
+        assert_eq!(
+            mixed.to_string(),
+            "maps: 4\n  keys: 1\n  hashes: 2\n  level IDs: 1\ncover: yes"
+        );
+    }
+
+    #[test]
+    fn sort_difficulty_lists() {
+        let difficulty = |name: &str, characteristic: &str| BeatmapDifficulty {
+            name: name.to_owned(),
+            characteristic: characteristic.to_owned(),
+        };
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(
+            Beatmap::new_key("1".to_owned())
+                .with_difficulty(difficulty("Expert", "standard"))
+                .with_difficulty(difficulty("Expert", "OneSaber"))
+                .with_difficulty(difficulty("Easy", "Standard"))
+                .with_difficulty(difficulty("Expert", "lawless")),
+        );
+        playlist.maps.push(
+            Beatmap::new_key("2".to_owned())
+                .with_difficulty(difficulty("Expert+", "Standard"))
+                .with_difficulty(difficulty("Hard", "Standard")),
+        );
+        playlist.maps.push(Beatmap::new_key("3".to_owned()));
+
+        playlist.sort_difficulty_lists();
+        assert_eq!(
+            playlist.maps[0].difficulties,
+            [
+                difficulty("Easy", "Standard"),
+                difficulty("Expert", "lawless"),
+                difficulty("Expert", "OneSaber"),
+                difficulty("Expert", "standard"),
+            ]
+        );
+        assert_eq!(
+            playlist.maps[1].difficulties,
+            [
+                difficulty("Hard", "Standard"),
+                difficulty("Expert+", "Standard")
+            ]
+        );
+        assert!(playlist.maps[2].difficulties.is_empty());
+    }
+
+    #[test]
+    fn missing_difficulties() {
+        let difficulty = BeatmapDifficulty {
+            name: "Expert".to_owned(),
+            characteristic: "Standard".to_owned(),
+        };
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .maps
+            .push(Beatmap::new_key("1".to_owned()).with_difficulty(difficulty.clone()));
+        assert!(!playlist.has_maps_without_difficulties());
+        assert_eq!(playlist.maps_with_missing_difficulties().count(), 0);
+
+        playlist.maps.push(Beatmap::new_key("2".to_owned()));
+        playlist
+            .maps
+            .push(Beatmap::new_key("3".to_owned()).with_difficulty(difficulty));
+        playlist.maps.push(Beatmap::new_level_id("4".to_owned()));
+        assert!(playlist.has_maps_without_difficulties());
+        let missing: Vec<(usize, Option<&str>)> = playlist
+            .maps_with_missing_difficulties()
+            .map(|(i, m)| (i, m.identifier_str()))
+            .collect();
+        assert_eq!(missing, [(1, Some("2")), (3, Some("4"))]);
+    }
+
+    #[test]
+    fn split_by_characteristic() {
+        let difficulty = |characteristic: &str| BeatmapDifficulty {
+            name: "Expert".to_owned(),
+            characteristic: characteristic.to_owned(),
+        };
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(
+            Beatmap::new_key("1".to_owned())
+                .with_difficulty(difficulty("Standard"))
+                .with_difficulty(difficulty("360Degree"))
+                .with_difficulty(BeatmapDifficulty {
+                    name: "Hard".to_owned(),
+                    ..difficulty("360Degree")
+                }),
+        );
+        playlist.maps.push(Beatmap::new_key("2".to_owned()));
+        playlist.maps.push(
+            Beatmap::new_key("3".to_owned())
+                .with_difficulty(difficulty("OneSaber"))
+                .with_difficulty(difficulty("Standard")),
+        );
+        playlist
+            .maps
+            .push(Beatmap::new_key("4".to_owned()).with_difficulty(difficulty("standard")));
+        playlist
+            .maps
+            .push(Beatmap::new_key("5".to_owned()).with_difficulty(difficulty("Rhythm")));
+        playlist
+            .maps
+            .push(Beatmap::new_key("6".to_owned()).with_difficulty(difficulty("rhythm")));
+
+        let split = playlist.split_by_characteristic();
+        assert_eq!(split.len(), 4);
+        assert_eq!(split["360Degree"].title, "playlist (360Degree)");
+        assert_eq!(split["360Degree"].maps, playlist.maps[..1]);
+        assert_eq!(
+            split["Standard"].maps,
+            [playlist.maps[1].clone(), playlist.maps[3].clone()]
+        );
+        assert_eq!(split["OneSaber"].maps, playlist.maps[2..3]);
+        assert_eq!(split["Rhythm"].title, "playlist (Rhythm)");
+        assert_eq!(split["Rhythm"].maps, playlist.maps[4..]);
+    }
 
-        let mut zip = ZipWriter::new(writer);
+    #[test]
+    fn diff() {
+        let mut old = Playlist::new("old".to_owned());
+        old.maps.push(Beatmap::new_key("1a".to_owned()));
+        old.maps.push(Beatmap::new_key("2b".to_owned()));
+        let mut new = Playlist::new("new".to_owned());
+        new.maps
+            .push(Beatmap::new_key("2B".to_owned()).with_date(Utc::now()));
+        new.maps.push(Beatmap::new_key("3c".to_owned()));
 
-        zip.start_file("playlist.json", Default::default())?;
-        serde_json::to_writer(&mut zip, &self)?;
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, [&new.maps[1]]);
+        assert_eq!(diff.removed, [&old.maps[0]]);
 
-        if let Some(c) = &self.cover {
-            zip.start_file_from_path(&c.path, Default::default())?;
-            zip.write_all(&c.data)?;
-        }
+        let mut disjoint = Playlist::new("disjoint".to_owned());
+        disjoint.maps.push(Beatmap::new_level_id("1a".to_owned()));
+        let diff = old.diff(&disjoint);
+        assert_eq!(diff.added, [&disjoint.maps[0]]);
+        assert_eq!(diff.removed, old.maps.iter().collect::<Vec<_>>());
 
-        zip.finish()?;
-        Ok(())
-    }
+        assert_eq!(old.maps_unique_to(&new), [&old.maps[0]]);
+        assert_eq!(new.maps_unique_to(&old), [&new.maps[1]]);
 
-    pub fn set_png_cover<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
-        let path = PathBuf::from("cover.png");
-        let mut data = Vec::new();
-        reader.read_to_end(&mut data)?;
-        let ty = PlaylistCoverType::Png;
+        let diff = old.diff(&old);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
 
-        if let Some(c) = self.cover.as_mut() {
-            c.path = path;
-            c.data = data;
-            c.ty = ty;
-        } else {
-            self.cover = Some(PlaylistCover { path, data, ty });
-        }
+        let (added, removed) = old.clone().diff_owned(new.clone());
+        assert_eq!(added, [new.maps[1].clone()]);
+        assert_eq!(removed, [old.maps[0].clone()]);
+    }
 
-        Ok(())
+    #[test]
+    fn date_buckets() {
+        let date = |s: &str| s.parse::<chrono::DateTime<Utc>>().unwrap();
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist
+            .maps
+            .push(Beatmap::new_key("1".to_owned()).with_date(date("2020-05-03T00:00:00Z")));
+        playlist.maps.push(Beatmap {
+            date: None,
+            ..Beatmap::new_key("2".to_owned())
+        });
+        playlist
+            .maps
+            .push(Beatmap::new_key("3".to_owned()).with_date(date("2020-05-30T00:00:00Z")));
+        playlist
+            .maps
+            .push(Beatmap::new_key("4".to_owned()).with_date(date("2019-12-31T00:00:00Z")));
+
+        let buckets = playlist.maps_by_date_bucket();
+        assert_eq!(
+            buckets.keys().collect::<Vec<_>>(),
+            [&(0, 0), &(2019, 12), &(2020, 5)]
+        );
+        assert_eq!(buckets[&(0, 0)], [&playlist.maps[1]]);
+        assert_eq!(buckets[&(2020, 5)], [&playlist.maps[0], &playlist.maps[2]]);
     }
-    pub fn set_jpg_cover<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
-        let path = PathBuf::from("cover.jpg");
-        let mut data = Vec::new();
-        reader.read_to_end(&mut data)?;
-        let ty = PlaylistCoverType::Jpg;
 
-        if let Some(c) = self.cover.as_mut() {
-            c.path = path;
-            c.data = data;
-            c.ty = ty;
-        } else {
-            self.cover = Some(PlaylistCover { path, data, ty });
-        }
+    #[test]
+    fn fingerprint_maps() {
+        let mut a = Playlist::new("a".to_owned());
+        a.maps.push(Beatmap::new_key("ABC".to_owned()));
+        a.maps.push(Beatmap::new_level_id("abc".to_owned()));
+        let mut b = Playlist::new("b".to_owned());
+        b.maps.push(Beatmap::new_level_id("abc".to_owned()));
+        b.maps
+            .push(Beatmap::new_key("abc".to_owned()).with_date(Utc::now()));
+        b.maps.push(Beatmap::new_key("Abc".to_owned()));
 
-        Ok(())
+        let fingerprint = a.fingerprint_maps();
+        assert_eq!(
+            fingerprint.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["key:abc", "levelID:abc"]
+        );
+        assert_eq!(b.fingerprint_maps(), fingerprint);
+
+        b.maps.remove(0);
+        assert_ne!(b.fingerprint_maps(), fingerprint);
     }
 
-    #[inline]
-    pub fn validate(&self) -> Result<(), Error> {
-        Ok(self.validate_inner(true)?)
+    #[test]
+    fn duplicates() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+
+        let mut no_duplicates = Playlist::new("playlist".to_owned());
+        no_duplicates.maps.push(Beatmap::new_key("16af".to_owned()));
+        no_duplicates.maps.push(Beatmap::new_hash(hash.to_owned()));
+        no_duplicates
+            .maps
+            .push(Beatmap::new_level_id("level ID".to_owned()));
+        assert!(no_duplicates.find_duplicates().is_empty());
+        assert!(!no_duplicates.has_duplicates());
+        let before = no_duplicates.clone();
+        no_duplicates.deduplicate_maps();
+        assert_eq!(no_duplicates, before);
+
+        let mut all_duplicates = Playlist::new("playlist".to_owned());
+        all_duplicates.maps.push(Beatmap::new_hash(hash.to_owned()));
+        all_duplicates
+            .maps
+            .push(Beatmap::new_hash(hash.to_uppercase()));
+        all_duplicates.maps.push(Beatmap::new_hash(hash.to_owned()));
+        assert_eq!(all_duplicates.find_duplicates(), [(0, 1), (0, 2)]);
+        assert!(all_duplicates.has_duplicates());
+        all_duplicates.deduplicate_maps();
+        assert_eq!(all_duplicates.maps.len(), 1);
+
+        let mut mixed = Playlist::new("playlist".to_owned());
+        mixed.maps.push(Beatmap::new_key("abc".to_owned()));
+        mixed.maps.push(Beatmap::new_level_id("abc".to_owned()));
+        mixed.maps.push(Beatmap::new_key("ABC".to_owned()));
+        mixed.maps.push(Beatmap::new_level_id("abc".to_owned()));
+        assert_eq!(mixed.find_duplicates(), [(0, 2), (1, 3)]);
+        assert!(mixed.has_duplicates());
+        assert!(!Playlist::new_with_maps(
+            "playlist".to_owned(),
+            vec![
+                Beatmap::new_key("abc".to_owned()),
+                Beatmap::new_level_id("abc".to_owned()),
+            ],
+        )
+        .has_duplicates());
+        mixed.deduplicate_maps();
+        assert_eq!(mixed.maps.len(), 2);
+        assert_eq!(mixed.maps[0].ty, BeatmapType::Key);
+        assert_eq!(mixed.maps[1].ty, BeatmapType::LevelId);
     }
 
-    pub(crate) fn validate_inner(&self, validate_cover: bool) -> Result<(), PlaylistError> {
-        if utils::str_is_empty_or_has_newlines(&self.title) {
-            return Err(PlaylistError::InvalidField {
-                field: "title",
-                value: self.title.clone(),
-            });
+    #[test]
+    fn duplicate_zip_entries() {
+        let playlist = Playlist::new("playlist".to_owned());
+
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        for name in &["playlist.json", "playlist.jsoX"] {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            serde_json::to_writer(&mut zip, &playlist).unwrap();
         }
-        if let Some(a) = &self.author {
-            if utils::str_is_empty_or_has_newlines(a) {
-                return Err(PlaylistError::InvalidField {
-                    field: "author",
-                    value: a.clone(),
-                });
+        zip.finish().unwrap();
+
+        // zip refuses to write duplicate names, so the second one is renamed afterwards
+        let mut bytes = buffer.into_inner();
+        for i in 0..bytes.len() - 12 {
+            if &bytes[i..i + 13] == b"playlist.jsoX" {
+                bytes[i + 12] = b'n';
             }
         }
-        if let Some(d) = &self.description {
-            if d.is_empty() {
-                return Err(PlaylistError::InvalidField {
-                    field: "description",
-                    value: d.clone(),
-                });
-            }
+        match Playlist::read(Cursor::new(&bytes)) {
+            Err(Error::DuplicateZipEntry { name }) => assert_eq!(name, "playlist.json"),
+            r => panic!("unexpected result: {:?}", r),
         }
+        match Playlist::read_cover_only(Cursor::new(&bytes)) {
+            Err(Error::DuplicateZipEntry { name }) => assert_eq!(name, "playlist.json"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn cover_path_traversal() {
+        for path in &[r"cover\..\evil.png", "/evil.png"] {
+            let mut buffer = Cursor::new(Vec::new());
+            let mut zip = ZipWriter::new(&mut buffer);
+            zip.start_file("playlist.json", SimpleFileOptions::default())
+                .unwrap();
+            serde_json::to_writer(
+                &mut zip,
+                &serde_json::json!({"title": "playlist", "maps": [], "cover": path}),
+            )
+            .unwrap();
+            zip.start_file(*path, SimpleFileOptions::default()).unwrap();
+            zip.write_all(PNG_MAGIC_NUMBER).unwrap();
+            zip.finish().unwrap();
 
-        if validate_cover {
-            if let Some(c) = &self.cover {
-                c.validate()?;
+            let bytes = buffer.into_inner();
+            match Playlist::read(Cursor::new(&bytes)) {
+                Err(Error::Validation(PlaylistError::InvalidCover(
+                    PlaylistCoverError::InvalidCoverPath { path: p, .. },
+                ))) => assert_eq!(p, Path::new(path)),
+                r => panic!("unexpected result for {}: {:?}", path, r),
+            }
+            match Playlist::read_cover_only(Cursor::new(&bytes)) {
+                Err(Error::Validation(PlaylistError::InvalidCover(
+                    PlaylistCoverError::InvalidCoverPath { .. },
+                ))) => (),
+                r => panic!("unexpected result for {}: {:?}", path, r),
             }
         }
+    }
 
-        for (idx, m) in self.maps.iter().enumerate() {
-            if let Err(error) = m.validate() {
-                return Err(PlaylistError::InvalidBeatmap { idx, error });
-            }
+    #[test]
+    fn json_error_position() {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("playlist.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"{\n  \"title\": \"playlist\",\n  \"maps\": [,]\n}")
+            .unwrap();
+        zip.finish().unwrap();
+
+        buffer.set_position(0);
+        match Playlist::read(&mut buffer) {
+            Err(Error::Json(e)) => assert_eq!((e.line(), e.column()), (3, 12)),
+            r => panic!("unexpected result: {:?}", r),
         }
+    }
 
-        Ok(())
+    #[test]
+    #[cfg(feature = "aes-crypto")]
+    fn encrypt_write() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+
+        let mut encrypted = Cursor::new(Vec::new());
+        playlist.encrypt_write(&mut encrypted, "password").unwrap();
+
+        let decrypted =
+            Playlist::decrypt_read(Cursor::new(encrypted.get_ref()), "password").unwrap();
+        assert_eq!(decrypted, playlist);
+
+        match Playlist::decrypt_read(Cursor::new(encrypted.get_ref()), "wrong") {
+            Err(Error::InvalidPassword) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(Playlist::read(Cursor::new(encrypted.get_ref())).is_err());
     }
-}
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
-pub struct PlaylistCover {
-    #[serde(rename = "cover")]
-    pub path: PathBuf,
-    #[serde(skip)]
-    pub data: Vec<u8>,
-    #[serde(skip)]
-    pub ty: PlaylistCoverType,
-}
+    #[test]
+    fn decrypt_read() {
+        let encrypted = include_bytes!("../fixtures/encrypted.blist");
 
-impl PlaylistCover {
-    pub(crate) fn validate(&self) -> Result<(), PlaylistCoverError> {
-        match self.ty {
-            PlaylistCoverType::Png => {
-                if utils::path_is_invalid(&self.path) || self.path.extension().unwrap() != "png" {
-                    return Err(PlaylistCoverError::InvalidCoverPath {
-                        ty: "png",
-                        path: self.path.clone(),
-                    });
-                }
-                if self.data.len() < PNG_MAGIC_NUMBER_LEN
-                    || !constant_time_eq::constant_time_eq(
-                        &self.data[..PNG_MAGIC_NUMBER_LEN],
-                        PNG_MAGIC_NUMBER,
-                    )
-                {
-                    return Err(PlaylistCoverError::InvalidCoverData { ty: "png" });
-                }
-            }
-            PlaylistCoverType::Jpg => {
-                if utils::path_is_invalid(&self.path) {
-                    return Err(PlaylistCoverError::InvalidCoverPath {
-                        ty: "jpg",
-                        path: self.path.clone(),
-                    });
-                }
-                let ext = self.path.extension().unwrap();
-                if ext != "jpg" && ext != "jpeg" {
-                    return Err(PlaylistCoverError::InvalidCoverPath {
-                        ty: "jpg",
-                        path: self.path.clone(),
-                    });
-                }
-                if self.data.len() < JPG_MAGIC_NUMBER_LEN
-                    || !constant_time_eq::constant_time_eq(
-                        &self.data[..JPG_MAGIC_NUMBER_LEN],
-                        JPG_MAGIC_NUMBER,
-                    )
-                {
-                    return Err(PlaylistCoverError::InvalidCoverData { ty: "jpg" });
-                }
-            }
-            PlaylistCoverType::Unknown => return Err(PlaylistCoverError::UnknownCoverType),
+        let playlist = Playlist::decrypt_read(Cursor::new(&encrypted[..]), "password").unwrap();
+        assert_eq!(playlist.title, "playlist");
+        assert_eq!(playlist.maps[0].key.as_deref(), Some("1a2b"));
+
+        match Playlist::decrypt_read(Cursor::new(&encrypted[..]), "wrong") {
+            Err(Error::InvalidPassword) => (),
+            r => panic!("unexpected result: {:?}", r),
         }
+        assert!(Playlist::read(Cursor::new(&encrypted[..])).is_err());
 
-        Ok(())
+        let unencrypted = Playlist::new("playlist".to_owned()).to_bytes().unwrap();
+        let playlist = Playlist::decrypt_read(Cursor::new(unencrypted), "password").unwrap();
+        assert_eq!(playlist.title, "playlist");
     }
-}
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum PlaylistCoverType {
-    Png,
-    Jpg,
-    Unknown,
-}
+    #[test]
+    fn bom() {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("playlist.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"\xEF\xBB\xBF{\"title\":\"playlist\",\"maps\":[]}")
+            .unwrap();
+        zip.finish().unwrap();
 
-impl Default for PlaylistCoverType {
-    #[inline]
-    fn default() -> Self {
-        Self::Unknown
+        buffer.set_position(0);
+        let playlist = Playlist::read(&mut buffer).unwrap();
+        assert_eq!(playlist.title, "playlist");
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::{
-        beatmap::BeatmapDifficulty,
-        playlist::{PlaylistCover, PlaylistCoverType},
-        Beatmap, Playlist,
-    };
-    use serde_json::Value;
-    use std::{io::Cursor, path::PathBuf};
 
     #[test]
-    fn write_and_read() {
-        let mut old = Playlist::new("playlist".to_owned());
-        old.author = Some("author".to_owned());
-        old.description = Some("description".to_owned());
-        old.custom_data
-            .insert("key".to_owned(), Value::String("value".to_owned()));
-
-        let mut map = Beatmap::new_key("16af".to_owned());
-        map.difficulties.push(BeatmapDifficulty {
-            name: "Expert+".to_owned(),
-            characteristic: "normal".to_owned(),
-        });
-        old.maps.push(map);
-        old.maps.push(Beatmap::new_hash(
-            "0123456789abcdef0123456789abcdef01234567".to_string(),
-        ));
-        old.maps.push(Beatmap::new_level_id("level ID".to_string()));
+    fn infer_missing_type() {
+        let json = br#"{"title":"playlist","maps":[
+            {"hash":"0123456789abcdef0123456789abcdef01234567"},
+            {"key":"16af"},
+            {"levelID":"level ID"}
+        ]}"#;
 
         let mut buffer = Cursor::new(Vec::new());
-        old.write(&mut buffer).unwrap();
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("playlist.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(json).unwrap();
+        zip.finish().unwrap();
 
         buffer.set_position(0);
-        let new = Playlist::read(&mut buffer).unwrap();
+        assert!(Playlist::read(&mut buffer).is_err());
 
-        assert_eq!(old, new);
+        buffer.set_position(0);
+        let options = ReadOptions {
+            infer_missing_type: true,
+        };
+        let playlist = Playlist::read_with_options(&mut buffer, options).unwrap();
+        let types: Vec<BeatmapType> = playlist.maps.iter().map(|m| m.ty).collect();
+        assert_eq!(
+            types,
+            [BeatmapType::Hash, BeatmapType::Key, BeatmapType::LevelId]
+        );
     }
 
     #[test]
@@ -374,6 +3259,12 @@ mod tests {
         playlist.maps.push(invalid_level_id);
         assert!(playlist.validate().is_err());
 
+        playlist.maps.clear();
+        let mut extra_field = Beatmap::new_key("16af".to_owned());
+        extra_field.hash = Some("0123456789abcdef0123456789abcdef01234567".to_owned());
+        playlist.maps.push(extra_field);
+        assert!(playlist.validate().is_err());
+
         playlist.maps.clear();
         let mut invalid_difficulty = Beatmap::new_key("16af".to_owned());
         invalid_difficulty.difficulties.push(BeatmapDifficulty {
@@ -382,5 +3273,292 @@ mod tests {
         });
         playlist.maps.push(invalid_difficulty);
         assert!(playlist.validate().is_err());
+
+        playlist.maps.clear();
+        let difficulty = BeatmapDifficulty {
+            name: "Expert".to_owned(),
+            characteristic: "Standard".to_owned(),
+        };
+        let duplicate_difficulty = Beatmap::new_key("16af".to_owned())
+            .with_difficulty(difficulty.clone())
+            .with_difficulty(BeatmapDifficulty {
+                name: "expert".to_owned(),
+                ..difficulty
+            });
+        playlist.maps.push(duplicate_difficulty);
+        assert!(playlist.validate().is_err());
+    }
+    #[test]
+    fn validate_all() {
+        let mut playlist = Playlist::new("".to_owned()).with_author("a\nb".to_owned());
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        playlist.maps.push(Beatmap {
+            key: None,
+            ..Beatmap::new_key("16af".to_owned())
+        });
+        playlist
+            .maps
+            .push(Beatmap::new_hash("not a hash".to_owned()));
+
+        let errors = playlist.validate_all().unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            errors[0],
+            Error::Validation(PlaylistError::InvalidField { field: "title", .. })
+        ));
+        assert!(matches!(
+            errors[1],
+            Error::Validation(PlaylistError::InvalidField {
+                field: "author",
+                ..
+            })
+        ));
+        assert!(matches!(
+            errors[2],
+            Error::Validation(PlaylistError::InvalidBeatmap { idx: 1, .. })
+        ));
+        assert!(matches!(
+            errors[3],
+            Error::Validation(PlaylistError::InvalidBeatmap { idx: 2, .. })
+        ));
+
+        assert!(Playlist::new("playlist".to_owned()).validate_all().is_ok());
+    }
+
+    #[test]
+    fn repair() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let mut playlist = Playlist::new("play\nlist".to_owned()).with_author("".to_owned());
+        playlist.description = Some("".to_owned());
+        playlist.maps.push(Beatmap {
+            hash: Some(hash.to_owned()),
+            ..Beatmap::new_key("16af".to_owned())
+        });
+        playlist.maps.push(Beatmap::new_key("not a key".to_owned()));
+        playlist.maps.push(Beatmap::new_hash(hash.to_owned()));
+
+        let (valid, changes) = playlist.validate_and_repair();
+        assert!(valid);
+        assert_eq!(
+            changes,
+            [
+                "joined the lines of the title",
+                "removed the empty author",
+                "removed the empty description",
+                "removed the extra hash of map 0",
+                "removed invalid map 1",
+            ]
+        );
+        assert_eq!(playlist.title, "play list");
+        assert_eq!(playlist.author, None);
+        assert_eq!(playlist.description, None);
+        assert_eq!(
+            playlist.maps,
+            [
+                Beatmap {
+                    date: playlist.maps[0].date,
+                    ..Beatmap::new_key("16af".to_owned())
+                },
+                Beatmap {
+                    date: playlist.maps[1].date,
+                    ..Beatmap::new_hash(hash.to_owned())
+                },
+            ]
+        );
+
+        let (valid, changes) = Playlist::new("".to_owned()).validate_and_repair();
+        assert!(!valid);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn partial_validation() {
+        let mut playlist = Playlist::new("".to_owned());
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        assert!(playlist.validate_maps_only().is_ok());
+        assert!(playlist.validate_metadata_only().is_err());
+
+        playlist.title = "playlist".to_owned();
+        playlist.maps.push(Beatmap::new_key("not a key".to_owned()));
+        assert!(playlist.validate_metadata_only().is_ok());
+        match playlist.validate_maps_only() {
+            Err(Error::Validation(PlaylistError::InvalidBeatmap { idx: 1, .. })) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn extra_identifiers_on_read() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        let mut map = Beatmap::new_key("16af".to_owned());
+        map.hash = Some("0123456789abcdef0123456789abcdef01234567".to_owned());
+        playlist.maps.push(map);
+        assert!(playlist.validate().is_err());
+
+        let json = serde_json::to_vec(&playlist).unwrap();
+        let read = Playlist::read_json(&json[..]).unwrap();
+        assert_eq!(read.maps[0].key.as_deref(), Some("16af"));
+        assert_eq!(read.maps[0].hash, None);
+        assert!(read.validate().is_ok());
+    }
+
+    #[test]
+    fn stale_schema() {
+        let json =
+            br#"{"$schema": "https://example.com/schema.json", "title": "playlist", "maps": []}"#;
+        let mut playlist = Playlist::read_json(&json[..]).unwrap();
+        assert!(playlist.schema_is_stale());
+        assert!(playlist.validate().is_ok());
+
+        let written = Playlist::from_json_value(playlist.to_json_value().unwrap()).unwrap();
+        assert!(!written.schema_is_stale());
+        assert_eq!(written, playlist);
+
+        assert_eq!(playlist.repair(), ["reset the schema URL"]);
+        assert!(!playlist.schema_is_stale());
+        assert!(!Playlist::new("playlist".to_owned()).schema_is_stale());
+    }
+
+    #[test]
+    fn tags() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        assert!(!playlist.has_tag("expert"));
+
+        playlist.add_tag("expert".to_owned());
+        playlist.add_tag("stream".to_owned());
+        playlist.add_tag("expert".to_owned());
+        assert_eq!(
+            playlist.tags,
+            Some(vec!["expert".to_owned(), "stream".to_owned()])
+        );
+        assert!(playlist.has_tag("stream"));
+        assert_eq!(
+            Playlist::from_bytes(&playlist.to_bytes().unwrap()).unwrap(),
+            playlist
+        );
+
+        assert!(playlist.has_any_tag(&["custom", "stream"]));
+        assert!(!playlist.has_any_tag(&["custom"]));
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        assert!(playlist.has_all_tags(&["expert", "stream"]));
+        assert!(!playlist.has_all_tags(&["expert", "custom"]));
+        assert!(playlist.has_all_tags(&[]));
+
+        let playlists = [playlist.clone(), Playlist::new("untagged".to_owned())];
+        let tagged = Playlist::filter_playlists_by_tag(&playlists, "expert");
+        assert_eq!(tagged, [&playlists[0]]);
+
+        playlist.remove_tag("expert");
+        playlist.remove_tag("stream");
+        assert_eq!(playlist.tags, None);
+
+        playlist.add_tag("expert".to_owned());
+        playlist.add_tag("multi\nline".to_owned());
+        match playlist.validate() {
+            Err(Error::Validation(e @ PlaylistError::InvalidTag { idx: 1, .. })) => {
+                assert_eq!(e.as_json_pointer(), "/tags/1")
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+        playlist.tags = Some(vec![String::new()]);
+        assert!(playlist.validate().is_err());
+    }
+
+    #[test]
+    fn future_dates() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1".to_owned()));
+        playlist.maps.push(
+            Beatmap::new_key("2".to_owned()).with_date(Utc::now() + chrono::Duration::days(2)),
+        );
+        assert!(playlist.validate().is_ok());
+
+        playlist.validator = Validator {
+            reject_future_dates: Some(chrono::Duration::hours(24)),
+            ..Default::default()
+        };
+        match playlist.validate() {
+            Err(Error::Validation(PlaylistError::InvalidBeatmap {
+                idx: 1,
+                error: BeatmapError::SuspiciousFutureDate { .. },
+            })) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        playlist.validator.reject_future_dates = Some(chrono::Duration::MAX);
+        assert!(playlist.validate().is_ok());
+    }
+
+    #[test]
+    fn json_pointer() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        let mut map = Beatmap::new_key("16af".to_owned());
+        map.difficulties.push(BeatmapDifficulty {
+            name: "Expert+".to_owned(),
+            characteristic: "Standard".to_owned(),
+        });
+        map.difficulties.push(BeatmapDifficulty {
+            name: "".to_owned(),
+            characteristic: "Standard".to_owned(),
+        });
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+        playlist.maps.push(map);
+
+        match playlist.validate() {
+            Err(Error::Validation(e)) => {
+                assert_eq!(e.as_json_pointer(), "/maps/1/difficulties/1/name")
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        playlist.title = "".to_owned();
+        match playlist.validate() {
+            Err(Error::Validation(e)) => assert_eq!(e.as_json_pointer(), "/title"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn validator() {
+        let mut playlist = Playlist::new("".to_owned());
+        playlist.description = Some("multiline\ndescription".to_owned());
+        playlist.maps.push(Beatmap::new_key("16af".to_owned()));
+
+        let default = Validator::default();
+        assert!(default.validate(&playlist).is_err());
+
+        let mut validator = Validator {
+            allow_empty_title: true,
+            ..Default::default()
+        };
+        assert!(validator.validate(&playlist).is_ok());
+
+        validator.allow_newlines_in_description = false;
+        assert!(validator.validate(&playlist).is_err());
+        validator.allow_newlines_in_description = true;
+
+        validator.max_maps = Some(0);
+        assert!(validator.validate(&playlist).is_err());
+        validator.max_maps = None;
+
+        validator.require_dates = true;
+        playlist.clear_all_dates();
+        assert!(validator.validate(&playlist).is_err());
+
+        playlist.validator = validator;
+        assert!(playlist.validate_map_at(0).is_err());
+        match playlist.validate_map_at(1) {
+            Err(Error::Validation(PlaylistError::MapIndexOutOfBounds { idx: 1, len: 1 })) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(playlist
+            .add_map(Beatmap {
+                date: None,
+                ..Beatmap::new_key("1a2b".to_owned())
+            })
+            .is_err());
+        assert!(playlist
+            .add_map(Beatmap::new_key("1a2b".to_owned()))
+            .is_ok());
     }
 }
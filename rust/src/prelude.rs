@@ -0,0 +1,6 @@
+pub use crate::{
+    beatmap::{Beatmap, BeatmapDifficulty, BeatmapType},
+    error::Error,
+    playlist::{Playlist, PlaylistCover, PlaylistCoverType},
+    validation::{BeatmapDifficultyError, BeatmapError, PlaylistError},
+};
@@ -0,0 +1,53 @@
+use crate::Playlist;
+use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(playlist: &Playlist, serializer: S) -> Result<S::Ok, S::Error> {
+    let bytes = playlist.to_bytes().map_err(ser::Error::custom)?;
+    serializer.serialize_str(&base64::encode(bytes))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Playlist, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    let bytes = base64::decode(&encoded).map_err(de::Error::custom)?;
+    Playlist::from_bytes(&bytes).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Beatmap, Playlist};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Wrapper {
+        #[serde(with = "crate::serde_zip_base64")]
+        playlist: Playlist,
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut playlist = Playlist::new("playlist".to_owned());
+        playlist.maps.push(Beatmap::new_key("1a2b".to_owned()));
+        let wrapper = Wrapper { playlist };
+
+        let value = serde_json::to_value(&wrapper).unwrap();
+        let encoded = value["playlist"].as_str().unwrap();
+        assert_eq!(
+            Playlist::from_bytes(&base64::decode(encoded).unwrap()).unwrap(),
+            wrapper.playlist
+        );
+        assert_eq!(serde_json::from_value::<Wrapper>(value).unwrap(), wrapper);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(serde_json::from_value::<Wrapper>(json!({ "playlist": "not base64!" })).is_err());
+        let not_zip = base64::encode(b"not a zip archive");
+        assert!(serde_json::from_value::<Wrapper>(json!({ "playlist": not_zip })).is_err());
+
+        let invalid = Wrapper {
+            playlist: Playlist::new("".to_owned()),
+        };
+        assert!(serde_json::to_value(&invalid).is_err());
+    }
+}
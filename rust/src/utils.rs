@@ -1,4 +1,8 @@
-use std::path::Path;
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::{Component, Path},
+};
+use zip::result::{ZipError, ZipResult};
 
 pub(crate) const PNG_MAGIC_NUMBER_LEN: usize = 8;
 pub(crate) const PNG_MAGIC_NUMBER: &[u8; PNG_MAGIC_NUMBER_LEN] =
@@ -7,6 +11,17 @@ pub(crate) const PNG_MAGIC_NUMBER: &[u8; PNG_MAGIC_NUMBER_LEN] =
 pub(crate) const JPG_MAGIC_NUMBER_LEN: usize = 3;
 pub(crate) const JPG_MAGIC_NUMBER: &[u8; JPG_MAGIC_NUMBER_LEN] = &[0xFF, 0xD8, 0xFF];
 
+pub(crate) const WEBP_MAGIC_NUMBER_LEN: usize = 12;
+/// Bytes 4 to 8 hold the file size and are ignored when comparing
+pub(crate) const WEBP_MAGIC_NUMBER: &[u8; WEBP_MAGIC_NUMBER_LEN] = b"RIFF\0\0\0\0WEBP";
+
+#[inline]
+pub(crate) fn webp_magic_number_matches(data: &[u8]) -> bool {
+    data.len() >= WEBP_MAGIC_NUMBER_LEN
+        && constant_time_eq::constant_time_eq(&data[..4], &WEBP_MAGIC_NUMBER[..4])
+        && constant_time_eq::constant_time_eq(&data[8..12], &WEBP_MAGIC_NUMBER[8..])
+}
+
 #[inline]
 pub(crate) fn str_is_empty_or_has_newlines(s: &str) -> bool {
     s.is_empty() || s.chars().any(|c| c == '\n' || c == '\r')
@@ -20,5 +35,208 @@ pub(crate) fn str_is_hex(s: &str) -> bool {
 #[inline]
 pub(crate) fn path_is_invalid<P: AsRef<Path>>(p: P) -> bool {
     let p = p.as_ref();
-    p.is_absolute() || p.extension().is_none() || p.parent() != Some(Path::new(""))
+    path_has_traversal(p)
+        || p.extension().is_none()
+        || p.parent().is_some_and(|p| p != Path::new(""))
+}
+
+#[inline]
+pub(crate) fn path_has_traversal<P: AsRef<Path>>(p: P) -> bool {
+    let p = p.as_ref();
+    p.has_root()
+        || p.components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        || p.to_string_lossy()
+            .split(&['/', '\\'][..])
+            .any(|s| s == "..")
+}
+
+const EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+const EOCD_LEN: usize = 22;
+const ZIP64_LOCATOR_SIGNATURE: &[u8] = b"PK\x06\x07";
+const ZIP64_LOCATOR_LEN: usize = 20;
+const ZIP64_EOCD_LEN: usize = 56;
+const CENTRAL_HEADER_SIGNATURE: &[u8] = b"PK\x01\x02";
+const CENTRAL_HEADER_LEN: usize = 46;
+
+#[inline]
+fn u16_at(b: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([b[i], b[i + 1]])
+}
+#[inline]
+fn u64_at(b: &[u8], i: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&b[i..i + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Lists the raw entry names from the central directory, in order.
+///
+/// `ZipArchive` only keeps the last entry with a given name, so this is the only way to notice
+/// duplicates. The central directory is assumed to end right before the end of central directory
+/// records, which also works for archives with prepended data.
+pub(crate) fn zip_entry_names<R: Read + Seek>(reader: &mut R) -> ZipResult<Vec<Vec<u8>>> {
+    let invalid = || ZipError::InvalidArchive("invalid central directory");
+
+    let len = reader.seek(SeekFrom::End(0))?;
+    let tail_start = len.saturating_sub((EOCD_LEN + usize::from(u16::MAX)) as u64);
+    reader.seek(SeekFrom::Start(tail_start))?;
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail)?;
+
+    let eocd = (0..=tail.len().checked_sub(EOCD_LEN).ok_or_else(invalid)?)
+        .rev()
+        .find(|&i| &tail[i..i + 4] == EOCD_SIGNATURE)
+        .ok_or_else(invalid)?;
+    let mut cd_size = u64::from(u32::from_le_bytes([
+        tail[eocd + 12],
+        tail[eocd + 13],
+        tail[eocd + 14],
+        tail[eocd + 15],
+    ]));
+    let mut cd_end = tail_start + eocd as u64;
+
+    let locator = eocd.checked_sub(ZIP64_LOCATOR_LEN);
+    if locator.is_some_and(|l| &tail[l..l + 4] == ZIP64_LOCATOR_SIGNATURE) {
+        cd_end = cd_end
+            .checked_sub((ZIP64_LOCATOR_LEN + ZIP64_EOCD_LEN) as u64)
+            .ok_or_else(invalid)?;
+        let mut zip64_eocd = [0; ZIP64_EOCD_LEN];
+        reader.seek(SeekFrom::Start(cd_end))?;
+        reader.read_exact(&mut zip64_eocd)?;
+        cd_size = u64_at(&zip64_eocd, 40);
+    }
+
+    let cd_start = cd_end.checked_sub(cd_size).ok_or_else(invalid)?;
+    reader.seek(SeekFrom::Start(cd_start))?;
+    let mut cd = Vec::new();
+    reader.by_ref().take(cd_size).read_to_end(&mut cd)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < cd.len() {
+        let header = cd.get(i..i + CENTRAL_HEADER_LEN).ok_or_else(invalid)?;
+        if &header[..4] != CENTRAL_HEADER_SIGNATURE {
+            return Err(invalid());
+        }
+        let name_len = usize::from(u16_at(header, 28));
+        let extra_len = usize::from(u16_at(header, 30));
+        let comment_len = usize::from(u16_at(header, 32));
+
+        let name_start = i + CENTRAL_HEADER_LEN;
+        let name = cd
+            .get(name_start..name_start + name_len)
+            .ok_or_else(invalid)?;
+        names.push(name.to_vec());
+        i = name_start + name_len + extra_len + comment_len;
+    }
+    Ok(names)
+}
+
+/// Reads the width and height from the IHDR chunk, which is always the first one
+pub(crate) fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 24 || &data[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    Some((width, height))
+}
+
+/// Walks the JPEG segments until the first start of frame marker
+pub(crate) fn jpg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    loop {
+        if *data.get(i)? != 0xFF {
+            return None;
+        }
+        // Markers can be preceded by any number of fill bytes
+        while *data.get(i + 1)? == 0xFF {
+            i += 1;
+        }
+        let marker = data[i + 1];
+        i += 2;
+        match marker {
+            0x01 | 0xD0..=0xD7 => continue,
+            0xD9 | 0xDA => return None,
+            _ => (),
+        }
+
+        let len = u16::from_be_bytes([*data.get(i)?, *data.get(i + 1)?]) as usize;
+        if let 0xC0..=0xCF = marker {
+            if !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let segment = data.get(i..i + 7)?;
+                let height = u16::from_be_bytes([segment[3], segment[4]]);
+                let width = u16::from_be_bytes([segment[5], segment[6]]);
+                return Some((width.into(), height.into()));
+            }
+        }
+        i += len;
+    }
+}
+
+/// Reads the canvas size from the first chunk, which depends on the WebP flavour
+pub(crate) fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let chunk = data.get(12..16)?;
+    let d = |i: usize| data.get(i).map(|&b| u32::from(b));
+    match chunk {
+        b"VP8 " => {
+            let width = (d(26)? | d(27)? << 8) & 0x3FFF;
+            let height = (d(28)? | d(29)? << 8) & 0x3FFF;
+            Some((width, height))
+        }
+        b"VP8L" => {
+            let bits = d(21)? | d(22)? << 8 | d(23)? << 16 | d(24)? << 24;
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8X" => {
+            let width = d(24)? | d(25)? << 8 | d(26)? << 16;
+            let height = d(27)? | d(28)? << 8 | d(29)? << 16;
+            Some((width + 1, height + 1))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{path_is_invalid, zip_entry_names};
+    use std::{
+        io::{Cursor, Write},
+        path::PathBuf,
+    };
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    #[test]
+    fn entry_names() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in &["playlist.json", "cover.png"] {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"data").unwrap();
+        }
+        zip.set_comment("comment");
+        let archive = zip.finish().unwrap().into_inner();
+
+        let names = zip_entry_names(&mut Cursor::new(archive.clone())).unwrap();
+        assert_eq!(names, [b"playlist.json".to_vec(), b"cover.png".to_vec()]);
+
+        let mut prepended = b"prepended data".to_vec();
+        prepended.extend_from_slice(&archive);
+        let mut reader = Cursor::new(prepended);
+        assert_eq!(zip_entry_names(&mut reader).unwrap(), names);
+        assert_eq!(reader.position(), 0);
+
+        assert!(zip_entry_names(&mut Cursor::new(b"not a zip archive".to_vec())).is_err());
+        assert!(zip_entry_names(&mut Cursor::new(&archive[..archive.len() / 2])).is_err());
+    }
+
+    #[test]
+    fn path_validation() {
+        assert!(!path_is_invalid("cover.png"));
+        assert!(path_is_invalid(PathBuf::from("subdir").join("cover.png")));
+        assert!(path_is_invalid(PathBuf::from("..").join("cover.png")));
+        assert!(path_is_invalid("cover"));
+        assert!(path_is_invalid(std::env::temp_dir().join("cover.png")));
+    }
 }
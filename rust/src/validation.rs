@@ -1,18 +1,277 @@
-use std::path::PathBuf;
+use crate::{playlist::PlaylistCover, utils, Beatmap, Playlist};
+use chrono::{DateTime, Duration, Utc};
+use std::{iter, path::PathBuf};
 use thiserror::Error;
 
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Validator {
+    pub allow_empty_title: bool,
+    pub allow_newlines_in_description: bool,
+    pub max_maps: Option<usize>,
+    pub require_dates: bool,
+    pub max_cover_bytes: Option<usize>,
+    /// Rejects maps dated further than this in the future, disabled by default since system
+    /// clocks can be wrong
+    pub reject_future_dates: Option<Duration>,
+}
+
+impl Default for Validator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            allow_empty_title: false,
+            allow_newlines_in_description: true,
+            max_maps: None,
+            require_dates: false,
+            max_cover_bytes: None,
+            reject_future_dates: None,
+        }
+    }
+}
+
+impl Validator {
+    #[inline]
+    pub fn validate(&self, playlist: &Playlist) -> Result<(), PlaylistError> {
+        self.validate_inner(playlist, true)
+    }
+
+    pub(crate) fn validate_inner(
+        &self,
+        playlist: &Playlist,
+        validate_cover: bool,
+    ) -> Result<(), PlaylistError> {
+        match self.errors(playlist, validate_cover).next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn validate_all_inner(
+        &self,
+        playlist: &Playlist,
+        validate_cover: bool,
+    ) -> Vec<PlaylistError> {
+        self.errors(playlist, validate_cover).collect()
+    }
+
+    // Errors are produced lazily so that only looking for the first one stops at it
+    fn errors<'a>(
+        &'a self,
+        playlist: &'a Playlist,
+        validate_cover: bool,
+    ) -> impl Iterator<Item = PlaylistError> + 'a {
+        self.metadata_errors(playlist, validate_cover)
+            .chain(self.maps_errors(playlist))
+    }
+
+    pub(crate) fn metadata_errors<'a>(
+        &'a self,
+        playlist: &'a Playlist,
+        validate_cover: bool,
+    ) -> impl Iterator<Item = PlaylistError> + 'a {
+        let title = iter::once_with(move || {
+            if !self.allow_empty_title || !playlist.title.is_empty() {
+                validate_title(&playlist.title).err()
+            } else {
+                None
+            }
+        });
+        let author = iter::once_with(move || {
+            playlist
+                .author
+                .as_deref()
+                .and_then(|a| validate_author(a).err())
+        });
+        let description = iter::once_with(move || match &playlist.description {
+            Some(d)
+                if d.is_empty()
+                    || (!self.allow_newlines_in_description
+                        && utils::str_is_empty_or_has_newlines(d)) =>
+            {
+                Some(PlaylistError::InvalidField {
+                    field: "description",
+                    value: d.clone(),
+                })
+            }
+            _ => None,
+        });
+        let tags = playlist
+            .tags
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, t)| utils::str_is_empty_or_has_newlines(t))
+            .map(|(idx, t)| PlaylistError::InvalidTag {
+                idx,
+                value: t.clone(),
+            });
+
+        let cover = iter::once_with(move || {
+            let c = playlist.cover.as_ref()?;
+            self.validate_cover(c, validate_cover).err().map(Into::into)
+        });
+        let cover_set = iter::once_with(move || {
+            let mut errors = Vec::new();
+            if let Some(cs) = &playlist.cover_set {
+                let mut paths = playlist.cover.iter().map(|c| &c.path).collect::<Vec<_>>();
+                for (field, c) in cs.entries() {
+                    let mut result = self.validate_cover(c, validate_cover);
+                    if result.is_ok() && paths.contains(&&c.path) {
+                        result = Err(PlaylistCoverError::DuplicateCoverPath {
+                            path: c.path.clone(),
+                        });
+                    }
+                    if let Err(error) = result {
+                        errors.push(PlaylistError::InvalidCoverSet { field, error });
+                    }
+                    paths.push(&c.path);
+                }
+            }
+            errors
+        });
+
+        title
+            .chain(author)
+            .chain(description)
+            .flatten()
+            .chain(tags)
+            .chain(cover.flatten())
+            .chain(cover_set.flatten())
+    }
+
+    pub(crate) fn maps_errors<'a>(
+        &'a self,
+        playlist: &'a Playlist,
+    ) -> impl Iterator<Item = PlaylistError> + 'a {
+        let max_maps = iter::once_with(move || match self.max_maps {
+            Some(max) if playlist.maps.len() > max => Some(PlaylistError::TooManyMaps {
+                count: playlist.maps.len(),
+                max,
+            }),
+            _ => None,
+        });
+        let now = Utc::now();
+        let maps = playlist
+            .maps
+            .iter()
+            .enumerate()
+            .filter_map(move |(idx, m)| {
+                let error = self.validate_map_at_time(m, now).err()?;
+                Some(PlaylistError::InvalidBeatmap { idx, error })
+            });
+
+        max_maps.flatten().chain(maps)
+    }
+
+    /// Validates a single map, including the checks that depend on the validator settings
+    #[inline]
+    pub fn validate_map(&self, map: &Beatmap) -> Result<(), BeatmapError> {
+        self.validate_map_at_time(map, Utc::now())
+    }
+
+    fn validate_map_at_time(&self, map: &Beatmap, now: DateTime<Utc>) -> Result<(), BeatmapError> {
+        map.validate()?;
+        if self.require_dates && map.date.is_none() {
+            return Err(BeatmapError::MissingField { field: "date" });
+        }
+        match (self.reject_future_dates, map.date) {
+            // A threshold too large to add to the current date can't be exceeded
+            (Some(threshold), Some(date))
+                if now
+                    .checked_add_signed(threshold)
+                    .is_some_and(|limit| date > limit) =>
+            {
+                Err(BeatmapError::SuspiciousFutureDate { date, threshold })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_cover(
+        &self,
+        cover: &PlaylistCover,
+        validate_data: bool,
+    ) -> Result<(), PlaylistCoverError> {
+        if validate_data {
+            cover.validate()?;
+        }
+        if let Some(max) = self.max_cover_bytes {
+            if cover.data.len() > max {
+                return Err(PlaylistCoverError::CoverTooLarge {
+                    size: cover.data.len(),
+                    max,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_title(title: &str) -> Result<(), PlaylistError> {
+    if utils::str_is_empty_or_has_newlines(title) {
+        return Err(PlaylistError::InvalidField {
+            field: "title",
+            value: title.to_owned(),
+        });
+    }
+    Ok(())
+}
+
+pub fn validate_author(author: &str) -> Result<(), PlaylistError> {
+    if utils::str_is_empty_or_has_newlines(author) {
+        return Err(PlaylistError::InvalidField {
+            field: "author",
+            value: author.to_owned(),
+        });
+    }
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 pub enum PlaylistError {
     #[error("playlist field `{field}` has value of `{value}` which doesn't respect the schema")]
     InvalidField { field: &'static str, value: String },
+    #[error(
+        "playlist tag at index `{idx}` has value of `{value}` which doesn't respect the schema"
+    )]
+    InvalidTag { idx: usize, value: String },
     #[error(transparent)]
     InvalidCover(#[from] PlaylistCoverError),
+    #[error("playlist cover set entry `{field}` is invalid: {error}")]
+    InvalidCoverSet {
+        field: &'static str,
+        #[source]
+        error: PlaylistCoverError,
+    },
     #[error("beatmap at index `{idx}` is invalid: {error}")]
     InvalidBeatmap {
         idx: usize,
         #[source]
         error: BeatmapError,
     },
+    #[error("playlist has `{count}` maps which is more than the maximum of `{max}`")]
+    TooManyMaps { count: usize, max: usize },
+    #[error("`{permutation:?}` is not a valid permutation of `{len}` maps")]
+    InvalidPermutation { permutation: Vec<usize>, len: usize },
+    #[error("map index `{idx}` is out of bounds for `{len}` maps")]
+    MapIndexOutOfBounds { idx: usize, len: usize },
+}
+
+impl PlaylistError {
+    pub fn as_json_pointer(&self) -> String {
+        match self {
+            Self::InvalidField { field, .. } => format!("/{}", field),
+            Self::InvalidTag { idx, .. } => format!("/tags/{}", idx),
+            Self::InvalidCover(_) => "/cover".to_owned(),
+            Self::InvalidCoverSet { field, .. } => format!("/{}", field),
+            Self::InvalidBeatmap { idx, error } => {
+                format!("/maps/{}{}", idx, error.as_json_pointer())
+            }
+            Self::TooManyMaps { .. }
+            | Self::InvalidPermutation { .. }
+            | Self::MapIndexOutOfBounds { .. } => "/maps".to_owned(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -23,6 +282,10 @@ pub enum PlaylistCoverError {
     InvalidCoverPath { ty: &'static str, path: PathBuf },
     #[error("playlist cover of type `{ty}` has invalid data")]
     InvalidCoverData { ty: &'static str },
+    #[error("playlist cover is `{size}` bytes which is more than the maximum of `{max}`")]
+    CoverTooLarge { size: usize, max: usize },
+    #[error("playlist cover path `{}` is used by more than one cover", .path.display())]
+    DuplicateCoverPath { path: PathBuf },
 }
 
 #[derive(Debug, Error)]
@@ -34,12 +297,47 @@ pub enum BeatmapError {
     },
     #[error("beatmap field `{field}` has value of `{value}` which doesn't respect the schema")]
     InvalidField { field: &'static str, value: String },
+    #[error("missing field `{field}` in beatmap")]
+    MissingField { field: &'static str },
+    #[error("beatmap field `{expected_none}` has value of `{value}` but shouldn't be set for the beatmap type")]
+    ExtraField {
+        expected_none: &'static str,
+        value: String,
+    },
     #[error("beatmap difficulty at index `{idx}` is invalid: {error}")]
     InvalidDifficulty {
         idx: usize,
         #[source]
         error: BeatmapDifficultyError,
     },
+    #[error("beatmap date `{date}` is more than `{threshold}` in the future")]
+    SuspiciousFutureDate {
+        date: DateTime<Utc>,
+        threshold: Duration,
+    },
+    #[error(
+        "beatmap has more than one `{name}` difficulty for the `{characteristic}` characteristic"
+    )]
+    DuplicateDifficulty {
+        name: String,
+        characteristic: String,
+    },
+}
+
+impl BeatmapError {
+    pub fn as_json_pointer(&self) -> String {
+        match self {
+            Self::MismatchedType { field, .. }
+            | Self::InvalidField { field, .. }
+            | Self::MissingField { field } => format!("/{}", field),
+            Self::ExtraField { expected_none, .. } => format!("/{}", expected_none),
+            Self::InvalidDifficulty { idx, error } => {
+                format!("/difficulties/{}{}", idx, error.as_json_pointer())
+            }
+            Self::DuplicateDifficulty { .. } => "/difficulties".to_owned(),
+            Self::SuspiciousFutureDate { .. } => "/date".to_owned(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -47,3 +345,11 @@ pub enum BeatmapDifficultyError {
     #[error("beatmap difficulty field `{field}` has value of `{value}` which doesn't respect the schema")]
     InvalidField { field: &'static str, value: String },
 }
+
+impl BeatmapDifficultyError {
+    pub fn as_json_pointer(&self) -> String {
+        match self {
+            Self::InvalidField { field, .. } => format!("/{}", field),
+        }
+    }
+}